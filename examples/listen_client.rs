@@ -0,0 +1,45 @@
+//! Stands in for an instrumented program: replays a profile to `phaseviz listen`,
+//! one code location every few milliseconds.
+//!
+//! cargo run --example listen_client -- /tmp/pv.sock assets/test/memviz.chekpoint.28516
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    os::unix::{fs::FileTypeExt, net::UnixStream},
+    thread,
+    time::Duration,
+};
+
+const DELAY: Duration = Duration::from_millis(300);
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.len() < 3 {
+        eprintln!("Usage: {} path_to_socket_or_fifo path_to_profile", args[0]);
+        std::process::exit(1);
+    }
+
+    let profile = fs::read_to_string(&args[2]).expect("Cannot read profile");
+
+    let is_fifo = fs::metadata(&args[1])
+        .map(|m| m.file_type().is_fifo())
+        .unwrap_or(false);
+
+    let mut out: Box<dyn Write> = if is_fifo {
+        Box::new(OpenOptions::new().write(true).open(&args[1]).unwrap())
+    } else {
+        Box::new(UnixStream::connect(&args[1]).expect("Cannot connect"))
+    };
+
+    for line in profile.lines() {
+        writeln!(out, "{}", line).unwrap();
+        out.flush().unwrap();
+
+        // Only code locations take time to appear
+        if line.contains("->") && line.contains('[') {
+            thread::sleep(DELAY);
+        }
+    }
+}
@@ -0,0 +1,637 @@
+use clap::{builder::PossibleValuesParser, Args, Parser, Subcommand, ValueEnum};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::config::Config;
+use crate::controller::{filter::Filter, fold, App};
+use crate::model::{
+    diff::diff,
+    export::{self, callgrind, csv, html, lcov, svg, Aggregation},
+    import,
+    profile::{Profile, ProfileItem},
+    stats::Stats,
+};
+use crate::ui::{
+    ansi_rows, plain_rows,
+    theme::{self, Theme, THEMES},
+};
+
+pub const EXIT_SUCCESS: i32 = 0;
+/// `diff` found differences, or `query` found no matching line
+pub const EXIT_NOT_SAME: i32 = 1;
+/// Invalid arguments, unreadable profile or configuration...
+pub const EXIT_FAILURE: i32 = 2;
+
+#[derive(Parser)]
+#[command(
+    name = "phaseviz",
+    version,
+    about = "TUI phase visualization tool",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+pub struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Without subcommand, the profiles are viewed
+    #[command(flatten)]
+    view: ViewArgs,
+}
+
+#[derive(Args)]
+struct GlobalArgs {
+    /// Directory where to look for the source files not found at their recorded path,
+    /// before the search paths of the configuration
+    #[arg(long, global = true, value_name = "DIR")]
+    source_dir: Vec<PathBuf>,
+    /// Configuration file, instead of $XDG_CONFIG_HOME/phaseviz/config.toml
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Colour theme, instead of the one of the configuration
+    #[arg(long, global = true, value_name = "NAME", value_parser = PossibleValuesParser::new(THEMES))]
+    theme: Option<String>,
+}
+
+#[derive(Args)]
+struct ViewArgs {
+    /// Reload the profile when it changes
+    #[arg(long)]
+    watch: bool,
+    /// Profiles to view, `n` and `p` switch between them
+    #[arg(required = true)]
+    profiles: Vec<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Browse profiles in the terminal (default)
+    View(ViewArgs),
+    /// Browse a profile streamed over a Unix socket or a FIFO
+    Listen {
+        /// Socket to create, or existing FIFO
+        path: PathBuf,
+    },
+    /// Print a summary of a profile: figures per checkpoint, debug information and
+    /// largest functions
+    Stats {
+        profile: PathBuf,
+        #[arg(long, value_enum, default_value_t = StatsFormat::Text)]
+        format: StatsFormat,
+    },
+    /// Write a profile in another format
+    Export(ExportArgs),
+    /// Write a report of a profile, with its source files
+    Report {
+        profile: PathBuf,
+        /// Directory of the HTML pages, readable offline
+        #[arg(long, value_name = "DIR", required = true)]
+        html: PathBuf,
+    },
+    /// Make a profile of coverage reports, each test being a checkpoint, and write it
+    /// as JSON. The other commands read it like a memviz profile.
+    Import {
+        #[arg(long, value_enum)]
+        format: ImportFormat,
+        /// Output file, instead of the standard output
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+    },
+    /// Print the lines whose checkpoints differ between two profiles.
+    /// Exits with 1 if there are some.
+    Diff { old: PathBuf, new: PathBuf },
+    /// Print the source files with the checkpoint matrix and the address range of each
+    /// line, coloured on a terminal. Exits with 1 if no line is selected.
+    Annotate {
+        profile: PathBuf,
+        /// Only the files whose path contains this text
+        #[arg(long, value_name = "TEXT")]
+        file: Option<String>,
+        /// Only the lines of the functions whose name contains this text
+        #[arg(long, value_name = "TEXT")]
+        function: Option<String>,
+    },
+    /// Print the lines matching a filter such as `cp:NAME !cp:ID file:TEXT fn:TEXT met`.
+    /// Exits with 1 if there are none.
+    Query {
+        profile: PathBuf,
+        #[arg(required = true, allow_hyphen_values = true)]
+        filter: Vec<String>,
+    },
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    profile: PathBuf,
+    #[arg(long, value_enum, default_value_t = ExportFormat::Text)]
+    format: ExportFormat,
+    /// What a row of a table or of a heatmap stands for, a line by default
+    #[arg(long, value_enum)]
+    aggregate: Option<Rows>,
+    /// Checkpoint whose coverage is exported, by name or id
+    #[arg(long, value_name = "CHECKPOINT", conflicts_with = "all")]
+    checkpoint: Option<String>,
+    /// Export the coverage of every checkpoint, in files of the output directory
+    #[arg(long)]
+    all: bool,
+    /// Output file, instead of the standard output. With `--all`, output directory
+    /// instead of the current one.
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum ExportFormat {
+    /// The checkpoint matrix, the address ranges and the source lines
+    Text,
+    /// The profile, without the source lines, see `phaseviz::model::profile::schema`
+    Json,
+    /// A row per line, with a 0/1 column per checkpoint
+    Csv,
+    /// Like csv, separated by tabs
+    Tsv,
+    /// The coverage of a checkpoint, as an lcov tracefile
+    Lcov,
+    /// A callgrind profile, with an event per checkpoint
+    Callgrind,
+    /// The checkpoint matrix as an SVG heatmap, a row per line, function or file
+    Svg,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum StatsFormat {
+    /// Aligned columns
+    Text,
+    /// See `phaseviz::model::stats::Stats`
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum ImportFormat {
+    /// lcov tracefiles, a checkpoint per test name or per tracefile
+    Lcov,
+    /// `llvm-cov export` JSON files, a checkpoint per file
+    LlvmCov,
+    /// callgrind or cachegrind profiles, a checkpoint per event or, for several parts,
+    /// per part
+    Callgrind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum Rows {
+    /// A line of the profile
+    Line,
+    /// Lines of a function, counting the lines that met each checkpoint
+    Function,
+    /// Lines of a file, counting the lines that met each checkpoint
+    File,
+}
+
+impl From<Rows> for Aggregation {
+    fn from(rows: Rows) -> Aggregation {
+        match rows {
+            Rows::Line => Aggregation::Line,
+            Rows::Function => Aggregation::Function,
+            Rows::File => Aggregation::File,
+        }
+    }
+}
+
+fn parse_profile(path: &Path) -> Result<Profile, String> {
+    Profile::try_parse(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn load_config(global: &GlobalArgs) -> Result<Config, String> {
+    let config = match &global.config {
+        Some(path) => Config::load(path),
+        None => Config::load_default(),
+    };
+    let mut config = config.map_err(|e| e.to_string())?;
+
+    match &global.theme {
+        Some(name) => config.theme = Theme::named(name)?,
+        None if theme::no_color() => config.theme = Theme::monochrome(),
+        None => {}
+    }
+
+    let mut search_paths = global.source_dir.clone();
+    search_paths.append(&mut config.search_paths);
+    config.search_paths = search_paths;
+
+    Ok(config)
+}
+
+fn write_output(output: Option<&Path>, text: &str) -> Result<(), String> {
+    match output {
+        Some(path) => std::fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e)),
+        None => std::io::stdout()
+            .write_all(text.as_bytes())
+            .map_err(|e| e.to_string()),
+    }
+}
+
+fn view(args: &ViewArgs, config: &Config) -> Result<i32, String> {
+    let profile = parse_profile(&args.profiles[0])?;
+
+    let mut app = App::new(&args.profiles[0], profile, config);
+    app.set_profiles(args.profiles.clone());
+    app.set_watch(args.watch);
+    app.run();
+
+    Ok(EXIT_SUCCESS)
+}
+
+fn listen(path: &Path, config: &Config) -> Result<i32, String> {
+    let profile = Profile::new(BTreeSet::new(), BTreeMap::new());
+
+    let mut app = App::new(path, profile, config);
+    app.set_listen(true);
+    app.run();
+
+    Ok(EXIT_SUCCESS)
+}
+
+fn stats(path: &Path, format: StatsFormat) -> Result<i32, String> {
+    let stats = Stats::new(&parse_profile(path)?);
+
+    let text = match format {
+        StatsFormat::Text => stats.to_string(),
+        StatsFormat::Json => {
+            let mut json = serde_json::to_string_pretty(&stats).map_err(|e| e.to_string())?;
+            json.push('\n');
+            json
+        }
+    };
+    write_output(None, &text)?;
+
+    Ok(EXIT_SUCCESS)
+}
+
+/// Write a tracefile per checkpoint, named after the checkpoint, in a directory
+fn export_lcov_all(profile: &Profile, directory: &Path) -> Result<(), String> {
+    std::fs::create_dir_all(directory).map_err(|e| format!("{}: {}", directory.display(), e))?;
+
+    for (id, name) in profile.checkpoints.iter() {
+        let path = directory.join(format!("{}-{}.info", id, export::identifier(name)));
+        write_output(Some(&path), &lcov::tracefile(profile, *id))?;
+    }
+
+    Ok(())
+}
+
+fn export(args: &ExportArgs, config: &Config) -> Result<i32, String> {
+    let profile = parse_profile(&args.profile)?;
+    let rows = match args.format {
+        ExportFormat::Csv | ExportFormat::Tsv | ExportFormat::Svg => {
+            args.aggregate.unwrap_or(Rows::Line)
+        }
+        _ if args.aggregate.is_some() => {
+            return Err(String::from(
+                "--aggregate only applies to the csv, tsv and svg formats",
+            ))
+        }
+        _ => Rows::Line,
+    };
+
+    if args.format != ExportFormat::Lcov {
+        if args.all {
+            return Err(String::from("--all only applies to the lcov format"));
+        }
+
+        if args.checkpoint.is_some() {
+            return Err(String::from("--checkpoint only applies to the lcov format"));
+        }
+    }
+
+    if args.all {
+        export_lcov_all(&profile, args.output.as_deref().unwrap_or(Path::new(".")))?;
+        return Ok(EXIT_SUCCESS);
+    }
+
+    let text = match args.format {
+        ExportFormat::Text => {
+            let profile = profile.synced_in(&config.search_paths);
+            let items: Vec<ProfileItem> = profile.items.iter().cloned().collect();
+
+            plain_rows(
+                &profile,
+                &items,
+                &fold::unfolded(&items),
+                config.view.tab_width,
+                &config.theme,
+            )
+        }
+        ExportFormat::Json => {
+            let mut json = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+            json.push('\n');
+            json
+        }
+        ExportFormat::Csv => csv::table(&profile, rows.into(), ','),
+        ExportFormat::Tsv => csv::table(&profile, rows.into(), '\t'),
+        ExportFormat::Lcov => match &args.checkpoint {
+            Some(checkpoint) => lcov::tracefile(&profile, profile.checkpoint_id(checkpoint)?),
+            None => return Err(String::from("the lcov format needs --checkpoint or --all")),
+        },
+        ExportFormat::Callgrind => callgrind::dump(&profile),
+        ExportFormat::Svg => svg::heatmap(&profile, rows.into()),
+    };
+
+    write_output(args.output.as_deref(), &text)?;
+
+    Ok(EXIT_SUCCESS)
+}
+
+fn report(path: &Path, directory: &Path, config: &Config) -> Result<i32, String> {
+    let profile = parse_profile(path)?.synced_in(&config.search_paths);
+    let title = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+
+    std::fs::create_dir_all(directory).map_err(|e| format!("{}: {}", directory.display(), e))?;
+
+    for (name, page) in html::report(&profile, &title, config.view.tab_width) {
+        write_output(Some(&directory.join(name)), &page)?;
+    }
+
+    Ok(EXIT_SUCCESS)
+}
+
+fn import_reports(
+    format: ImportFormat,
+    reports: &[PathBuf],
+    output: Option<&Path>,
+) -> Result<i32, String> {
+    let profile = match format {
+        ImportFormat::Lcov => import::lcov::import(reports)?,
+        ImportFormat::LlvmCov => import::llvm_cov::import(reports)?,
+        ImportFormat::Callgrind => import::callgrind::import(reports)?,
+    };
+
+    let mut json = serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())?;
+    json.push('\n');
+    write_output(output, &json)?;
+
+    Ok(EXIT_SUCCESS)
+}
+
+fn format_names(names: &Option<BTreeSet<String>>) -> String {
+    match names {
+        Some(names) if !names.is_empty() => names.iter().cloned().collect::<Vec<_>>().join(","),
+        _ => String::from("-"),
+    }
+}
+
+fn print_diff(old_path: &Path, new_path: &Path) -> Result<i32, String> {
+    let diffs = diff(&parse_profile(old_path)?, &parse_profile(new_path)?);
+
+    if diffs.is_empty() {
+        return Ok(EXIT_SUCCESS);
+    }
+
+    println!("--- {}", old_path.display());
+    println!("+++ {}", new_path.display());
+
+    for d in diffs.iter() {
+        let (path, nb, function) = &d.key;
+        let mut location = format!("{}:{}", path.expand().display(), nb);
+
+        if let Some(function) = function {
+            location.push_str(&format!(" ({})", function));
+        }
+
+        match (&d.old, &d.new) {
+            (None, _) => println!("+ {} {}", location, format_names(&d.new)),
+            (_, None) => println!("- {} {}", location, format_names(&d.old)),
+            _ => println!(
+                "~ {} {} -> {}",
+                location,
+                format_names(&d.old),
+                format_names(&d.new)
+            ),
+        }
+    }
+
+    Ok(EXIT_NOT_SAME)
+}
+
+/// The lines of the files, or of the functions, matching the texts, under the header of
+/// their file. The lines of a function go from its first to its last line in the
+/// profile.
+fn annotated_items(
+    items: &[ProfileItem],
+    file: Option<&str>,
+    function: Option<&str>,
+) -> Vec<ProfileItem> {
+    let mut selected = vec![];
+    let mut start = 0;
+
+    while start < items.len() {
+        let end = items[start + 1..]
+            .iter()
+            .position(|item| item.is_file())
+            .map_or(items.len(), |i| start + 1 + i);
+        let section = &items[start..end];
+        start = end;
+
+        let path = section[0].get_file_info().borrow().path.expand();
+        if file.is_some_and(|text| !path.to_string_lossy().contains(text)) {
+            continue;
+        }
+
+        let in_function = |item: &ProfileItem| match (item, function) {
+            (ProfileItem::Line(_, l), Some(text)) => {
+                l.function.as_ref().is_some_and(|f| f.contains(text))
+            }
+            _ => false,
+        };
+        let first = section.iter().position(in_function);
+        let last = section.iter().rposition(in_function);
+
+        let lines = match (function, first, last) {
+            (None, _, _) => &section[1..],
+            (Some(_), Some(first), Some(last)) => &section[first..=last],
+            (Some(_), _, _) => continue,
+        };
+
+        selected.push(section[0].clone());
+        selected.extend(
+            lines
+                .iter()
+                .filter(|item| match item {
+                    // Without debug information, lines are only grouped by function
+                    ProfileItem::Line(_, l) if !l.has_debug_info => {
+                        function.is_none() || in_function(item)
+                    }
+                    _ => true,
+                })
+                .cloned(),
+        );
+    }
+
+    selected
+}
+
+fn annotate(
+    path: &Path,
+    file: Option<&str>,
+    function: Option<&str>,
+    config: &Config,
+) -> Result<i32, String> {
+    let profile = parse_profile(path)?.synced_in(&config.search_paths);
+    let items: Vec<ProfileItem> = profile.items.iter().cloned().collect();
+    let items = annotated_items(&items, file, function);
+
+    if items.is_empty() {
+        return Ok(EXIT_NOT_SAME);
+    }
+
+    let rows = match config.view.fold {
+        true => fold::folded(&items, config.view.fold_context, &BTreeSet::new()),
+        false => fold::unfolded(&items),
+    };
+    let render = match std::io::stdout().is_terminal() {
+        true => ansi_rows,
+        false => plain_rows,
+    };
+
+    let text = render(
+        &profile,
+        &items,
+        &rows,
+        config.view.tab_width,
+        &config.theme,
+    );
+    write_output(None, &text)?;
+
+    Ok(EXIT_SUCCESS)
+}
+
+fn query(path: &Path, filter: &[String], config: &Config) -> Result<i32, String> {
+    let profile = parse_profile(path)?.synced_in(&config.search_paths);
+    let items: Vec<ProfileItem> = profile.items.iter().cloned().collect();
+
+    let filter = Filter::parse(&filter.join(" "), &profile)?;
+    let rows = filter.rows(&items);
+
+    if rows.is_empty() {
+        return Ok(EXIT_NOT_SAME);
+    }
+
+    let text = plain_rows(
+        &profile,
+        &items,
+        &rows,
+        config.view.tab_width,
+        &config.theme,
+    );
+    write_output(None, &text)?;
+
+    Ok(EXIT_SUCCESS)
+}
+
+/// Run the command line, and return the exit code
+pub fn run(cli: Cli) -> Result<i32, String> {
+    let config = load_config(&cli.global)?;
+
+    match &cli.command {
+        None => view(&cli.view, &config),
+        Some(Command::View(args)) => view(args, &config),
+        Some(Command::Listen { path }) => listen(path, &config),
+        Some(Command::Stats { profile, format }) => stats(profile, *format),
+        Some(Command::Export(args)) => export(args, &config),
+        Some(Command::Report { profile, html }) => report(profile, html, &config),
+        Some(Command::Import {
+            format,
+            output,
+            reports,
+        }) => import_reports(*format, reports, output.as_deref()),
+        Some(Command::Diff { old, new }) => print_diff(old, new),
+        Some(Command::Annotate {
+            profile,
+            file,
+            function,
+        }) => annotate(profile, file.as_deref(), function.as_deref(), &config),
+        Some(Command::Query { profile, filter }) => query(profile, filter, &config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{annotated_items, export, Cli, Command};
+    use crate::config::Config;
+    use crate::model::profile::{Profile, ProfileItem};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn parse_arguments() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from(["phaseviz", "--watch", "a", "b"]).unwrap();
+        assert!(cli.command.is_none());
+        assert!(cli.view.watch);
+        assert_eq!(cli.view.profiles.len(), 2);
+
+        let cli = Cli::try_parse_from(["phaseviz", "diff", "a", "b", "--theme", "monochrome"]);
+        assert_eq!(cli.unwrap().global.theme.as_deref(), Some("monochrome"));
+
+        assert!(Cli::try_parse_from(["phaseviz"]).is_err());
+        assert!(Cli::try_parse_from(["phaseviz", "a", "--theme", "solarized"]).is_err());
+        assert!(Cli::try_parse_from(["phaseviz", "query", "a", "!cp:0"]).is_ok());
+
+        let export = ["phaseviz", "export", "a", "--format", "lcov", "--all"];
+        assert!(Cli::try_parse_from(export).is_ok());
+        assert!(Cli::try_parse_from([&export[..], &["--checkpoint", "0"]].concat()).is_err());
+    }
+
+    #[test]
+    fn export_options() {
+        let run = |options: &[&str]| {
+            let profile = "assets/test/memviz.chekpoint.28516";
+            let args = [&["phaseviz", "export", profile], options].concat();
+            match Cli::try_parse_from(args).unwrap().command {
+                Some(Command::Export(args)) => export(&args, &Config::default()),
+                _ => unreachable!(),
+            }
+        };
+
+        assert!(run(&["--format", "csv", "--checkpoint", "0"]).is_err());
+        assert!(run(&["--format", "json", "--aggregate", "file"]).is_err());
+        assert!(run(&["--format", "svg", "--all"]).is_err());
+        assert!(run(&["--format", "lcov"]).is_err());
+        assert!(run(&["--format", "csv", "--aggregate", "file", "-o", "/dev/null"]).is_ok());
+    }
+
+    #[test]
+    fn annotate_items() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516").synced();
+        let items: Vec<ProfileItem> = profile.items.iter().cloned().collect();
+        let numbers = |items: Vec<ProfileItem>| -> Vec<usize> {
+            items
+                .iter()
+                .map(|item| match item {
+                    ProfileItem::File(_) => 0,
+                    ProfileItem::Line(_, l) => l.nb,
+                })
+                .collect()
+        };
+
+        assert_eq!(annotated_items(&items, None, None).len(), items.len());
+        assert_eq!(
+            annotated_items(&items, Some("hello.c"), None).len(),
+            items.len()
+        );
+        assert!(annotated_items(&items, Some("world.c"), None).is_empty());
+        assert_eq!(
+            numbers(annotated_items(&items, Some("hello"), Some("ma"))),
+            vec![0, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19]
+        );
+        assert!(annotated_items(&items, None, Some("printf")).is_empty());
+    }
+}
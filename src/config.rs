@@ -0,0 +1,324 @@
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::controller::{check_tab_width, keymap::Keymap, DEFAULT_FOLD_CONTEXT, DEFAULT_TAB_WIDTH};
+use crate::ui::{
+    layout::{PanelLayout, PanelSlot},
+    theme::{ColorValue, Theme},
+};
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "cannot read {}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "in {}: {}", path.display(), e),
+            ConfigError::Invalid(path, e) => write!(f, "in {}: {}", path.display(), e),
+        }
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawLayout {
+    panels: Vec<PanelSlot>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawView {
+    fold: Option<bool>,
+    fold_context: Option<usize>,
+    tab_width: Option<usize>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawSource {
+    search_paths: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct RawConfig {
+    /// Name of a built-in theme
+    theme: Option<String>,
+    layout: Option<RawLayout>,
+    /// Key names to command lines
+    keys: BTreeMap<String, String>,
+    colors: BTreeMap<String, ColorValue>,
+    view: RawView,
+    source: RawSource,
+}
+
+/// How the rows are displayed at startup
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ViewConfig {
+    pub fold: bool,
+    pub fold_context: usize,
+    pub tab_width: usize,
+}
+
+impl Default for ViewConfig {
+    fn default() -> Self {
+        ViewConfig {
+            fold: false,
+            fold_context: DEFAULT_FOLD_CONTEXT,
+            tab_width: DEFAULT_TAB_WIDTH,
+        }
+    }
+}
+
+/// User configuration, every section being optional:
+///
+/// ```toml
+/// theme = "deuteranopia"  # default, high-contrast, monochrome or deuteranopia
+///
+/// [layout]
+/// panels = [{ panel = "checkpoints", size = "20%" }, { panel = "source", size = "fill" }]
+///
+/// [keys]          # key name = command line, "" to unbind
+/// j = "down"
+/// ctrl-f = "page-down"
+///
+/// [colors]        # on top of the theme: colour name, palette index or #rrggbb
+/// met = "light-green"
+/// focus = "17"
+///
+/// [view]
+/// fold = true
+/// fold_context = 3
+/// tab_width = 4
+///
+/// [source]
+/// search_paths = ["~/src/project"]
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub layout: PanelLayout,
+    pub keymap: Keymap,
+    pub theme: Theme,
+    pub view: ViewConfig,
+    /// Directories where to look for the source files that are not found at their
+    /// recorded path
+    pub search_paths: Vec<PathBuf>,
+}
+
+/// Expand a leading `~` to the home directory and make a relative path relative to
+/// the directory of the configuration file
+fn expand_path(path: &str, config_path: &Path) -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    let path = match (path.strip_prefix("~/"), home) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    };
+
+    match config_path.parent() {
+        Some(dir) if path.is_relative() => dir.join(path),
+        _ => path,
+    }
+}
+
+impl Config {
+    /// Default location of the configuration file:
+    /// `$XDG_CONFIG_HOME/phaseviz/config.toml`, `$XDG_CONFIG_HOME` defaulting to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config_home = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+        };
+
+        Some(config_home.join("phaseviz").join("config.toml"))
+    }
+
+    pub fn parse<P: AsRef<Path>>(content: &str, path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref().to_path_buf();
+        let raw: RawConfig =
+            toml::from_str(content).map_err(|e| ConfigError::Parse(path.clone(), e))?;
+
+        let layout = match raw.layout {
+            Some(layout) if !layout.panels.is_empty() => PanelLayout::new(layout.panels)
+                .map_err(|e| ConfigError::Invalid(path.clone(), e))?,
+            _ => PanelLayout::default(),
+        };
+
+        let mut keymap = Keymap::default();
+        for (key, command) in raw.keys.iter() {
+            keymap.bind(key, command).map_err(|e| {
+                ConfigError::Invalid(path.clone(), format!("[keys] {}: {}", key, e))
+            })?;
+        }
+
+        let theme = match &raw.theme {
+            Some(name) => Theme::named(name).map_err(|e| ConfigError::Invalid(path.clone(), e))?,
+            None => Theme::default(),
+        };
+
+        let theme = theme
+            .with_colors(&raw.colors)
+            .map_err(|e| ConfigError::Invalid(path.clone(), format!("[colors] {}", e)))?;
+
+        let defaults = ViewConfig::default();
+        let view = ViewConfig {
+            fold: raw.view.fold.unwrap_or(defaults.fold),
+            fold_context: raw.view.fold_context.unwrap_or(defaults.fold_context),
+            tab_width: raw.view.tab_width.unwrap_or(defaults.tab_width),
+        };
+
+        if let Err(e) = check_tab_width(view.tab_width) {
+            return Err(ConfigError::Invalid(path, format!("[view] {}", e)));
+        }
+
+        let search_paths = raw
+            .source
+            .search_paths
+            .iter()
+            .map(|p| expand_path(p, &path))
+            .collect();
+
+        Ok(Config {
+            layout,
+            keymap,
+            theme,
+            view,
+            search_paths,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let content =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+
+        Config::parse(&content, path)
+    }
+
+    /// Load the configuration from its default location, if it exists
+    pub fn load_default() -> Result<Config, ConfigError> {
+        match Config::default_path() {
+            Some(path) if path.exists() => Config::load(path),
+            _ => Ok(Config::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use crate::ui::layout::{PanelKind, PanelLayout, PanelSize, PanelSlot};
+    use crate::ui::theme::Theme;
+    use std::path::PathBuf;
+    use termion::event::Key;
+    use tui::style::Color;
+
+    #[test]
+    fn parse_layout() {
+        let config = Config::parse(
+            r#"
+            [layout]
+            panels = [
+                { panel = "source", size = "fill" },
+                { panel = "checkpoints", size = 40 },
+                { panel = "addresses", size = "20%", visible = false },
+            ]
+            "#,
+            "config.toml",
+        )
+        .unwrap();
+
+        let expected = PanelLayout::new(vec![
+            PanelSlot {
+                panel: PanelKind::Source,
+                size: PanelSize::Fill,
+                visible: true,
+            },
+            PanelSlot {
+                panel: PanelKind::Checkpoints,
+                size: PanelSize::Length(40),
+                visible: true,
+            },
+            PanelSlot {
+                panel: PanelKind::Addresses,
+                size: PanelSize::Percentage(20),
+                visible: false,
+            },
+        ])
+        .unwrap();
+
+        assert_eq!(config.layout, expected);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Config::parse("[layout]\npanels = [{ panel = \"foo\", size = 3 }]", "c").is_err());
+        assert!(Config::parse(
+            "[layout]\npanels = [{ panel = \"source\", size = \"x\" }]",
+            "c"
+        )
+        .is_err());
+        assert!(Config::parse("[layuot]", "c").is_err());
+        assert!(Config::parse("", "c").is_ok());
+        assert!(Config::parse("[keys]\nhyper-x = \"quit\"", "c").is_err());
+        assert!(Config::parse("[keys]\nx = \"explode\"", "c").is_err());
+        assert!(Config::parse("[colors]\nmet = \"grene\"", "c").is_err());
+        assert!(Config::parse("[view]\ntab_width = 0", "c").is_err());
+        assert!(Config::parse("[view]\nfolding = true", "c").is_err());
+        assert!(Config::parse("theme = \"solarized\"", "c").is_err());
+    }
+
+    #[test]
+    fn parse_sections() {
+        let config = Config::parse(
+            r##"
+            theme = "high-contrast"
+
+            [keys]
+            j = "down"
+            ctrl-d = "page-down"
+            q = ""
+
+            [colors]
+            met = "#00ff00"
+            focus = "236"
+
+            [view]
+            fold = true
+            tab_width = 4
+
+            [source]
+            search_paths = ["/usr/src", "src"]
+            "##,
+            "/home/user/.config/phaseviz/config.toml",
+        )
+        .unwrap();
+
+        assert_eq!(config.keymap.get(Key::Char('j')), Some("down"));
+        assert_eq!(config.keymap.get(Key::Ctrl('d')), Some("page-down"));
+        assert_eq!(config.keymap.get(Key::Char('q')), None);
+        assert_eq!(config.keymap.get(Key::Up), Some("up"));
+        assert_eq!(config.theme.met.fg, Some(Color::Rgb(0, 0xff, 0)));
+        assert_eq!(config.theme.focus.bg, Some(Color::Indexed(236)));
+        assert_eq!(config.theme.not_met, Theme::high_contrast().not_met);
+        assert!(config.view.fold);
+        assert_eq!(config.view.fold_context, 3);
+        assert_eq!(config.view.tab_width, 4);
+        assert_eq!(
+            config.search_paths,
+            vec![
+                PathBuf::from("/usr/src"),
+                PathBuf::from("/home/user/.config/phaseviz/src")
+            ]
+        );
+    }
+}
@@ -0,0 +1,707 @@
+use super::{check_tab_width, filter, App};
+use crate::ui::layout::PanelKind;
+
+/// What the arguments of a command can be completed with
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Completion {
+    Nothing,
+    Checkpoint,
+    Command,
+    Filter,
+    Option,
+    Panel,
+    Path,
+}
+
+type Action = fn(&mut App, &[&str]) -> Result<(), String>;
+
+/// A command that can be typed after `:` or bound to a key
+pub struct Command {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub completion: Completion,
+    run: Action,
+}
+
+/// Options of the `set` command
+pub const OPTIONS: [&str; 2] = ["tabwidth=", "foldcontext="];
+
+fn count(args: &[&str]) -> Result<usize, String> {
+    match args.first() {
+        Some(n) => n.parse().map_err(|_| format!("invalid count {:?}", n)),
+        None => Ok(1),
+    }
+}
+
+fn no_args(args: &[&str]) -> Result<(), String> {
+    match args.is_empty() {
+        true => Ok(()),
+        false => Err(String::from("this command takes no argument")),
+    }
+}
+
+fn one_arg<'a>(args: &[&'a str]) -> Result<&'a str, String> {
+    match args {
+        [arg] => Ok(arg),
+        [] => Err(String::from("missing argument")),
+        _ => Err(String::from("too many arguments")),
+    }
+}
+
+fn panel_arg(args: &[&str]) -> Result<PanelKind, String> {
+    let name = one_arg(args)?;
+
+    PanelKind::ALL
+        .iter()
+        .find(|kind| kind.name() == name)
+        .cloned()
+        .ok_or_else(|| format!("unknown panel {:?}", name))
+}
+
+fn set_option(app: &mut App, args: &[&str]) -> Result<(), String> {
+    let arg = one_arg(args)?;
+    let (option, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected option=value, got {:?}", arg))?;
+    let value: usize = value
+        .parse()
+        .map_err(|_| format!("invalid value {:?}", value))?;
+
+    match option {
+        "tabwidth" => {
+            check_tab_width(value)?;
+            app.set_tab_width(value)
+        }
+        "foldcontext" => app.set_fold_context(value),
+        _ => return Err(format!("unknown option {:?}", option)),
+    }
+
+    Ok(())
+}
+
+fn fold_context(app: &mut App, args: &[&str]) -> Result<(), String> {
+    let arg = one_arg(args)?;
+    let n: isize = arg
+        .parse()
+        .map_err(|_| format!("invalid context {:?}", arg))?;
+
+    let context = match arg.starts_with(['+', '-']) {
+        true if n < 0 => app.fold_context.saturating_sub(n.unsigned_abs()),
+        true => app.fold_context.saturating_add(n as usize),
+        false => n.max(0) as usize,
+    };
+
+    app.set_fold_context(context);
+    Ok(())
+}
+
+fn move_panel(app: &mut App, args: &[&str]) -> Result<(), String> {
+    match one_arg(args)? {
+        "left" => app.layout.move_selected(-1),
+        "right" => app.layout.move_selected(1),
+        arg => return Err(format!("expected left or right, got {:?}", arg)),
+    }
+
+    Ok(())
+}
+
+/// All the commands, in the order they are listed in completions
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "up",
+        usage: "up [N]",
+        help: "Move the cursor up",
+        completion: Completion::Nothing,
+        run: |app, args| count(args).map(|n| app.move_up(n)),
+    },
+    Command {
+        name: "down",
+        usage: "down [N]",
+        help: "Move the cursor down",
+        completion: Completion::Nothing,
+        run: |app, args| count(args).map(|n| app.move_down(n)),
+    },
+    Command {
+        name: "page-up",
+        usage: "page-up",
+        help: "Move the cursor one screen up",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.move_up(app.get_height())),
+    },
+    Command {
+        name: "page-down",
+        usage: "page-down",
+        help: "Move the cursor one screen down",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.move_down(app.get_height())),
+    },
+    Command {
+        name: "left",
+        usage: "left [N]",
+        help: "Scroll the source to the left",
+        completion: Completion::Nothing,
+        run: |app, args| {
+            count(args).map(|n| app.scroll_left(n.saturating_mul(super::H_SCROLL_STEP)))
+        },
+    },
+    Command {
+        name: "right",
+        usage: "right [N]",
+        help: "Scroll the source to the right",
+        completion: Completion::Nothing,
+        run: |app, args| {
+            count(args).map(|n| app.scroll_right(n.saturating_mul(super::H_SCROLL_STEP)))
+        },
+    },
+    Command {
+        name: "home",
+        usage: "home",
+        help: "Scroll the source back to the first column",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.h_offset = 0),
+    },
+    Command {
+        name: "goto",
+        usage: "goto LINE|0xADDRESS",
+        help: "Go to a line of the current file, or to the line of an address",
+        completion: Completion::Nothing,
+        run: |app, args| app.goto(one_arg(args)?),
+    },
+    Command {
+        name: "filter",
+        usage: "filter [cp:ID|NAME] [file:TEXT] [fn:TEXT] [met] [!TERM]",
+        help: "Only display matching lines, or every line without argument",
+        completion: Completion::Filter,
+        run: |app, args| app.set_filter(&args.join(" ")),
+    },
+    Command {
+        name: "focus",
+        usage: "focus [ID|NAME]",
+        help: "Highlight the lines that met a checkpoint, or stop highlighting",
+        completion: Completion::Checkpoint,
+        run: |app, args| match args {
+            [] => {
+                app.set_focus(None);
+                Ok(())
+            }
+            [cp] => app
+                .profile
+                .checkpoint_id(cp)
+                .map(|id| app.set_focus(Some(id))),
+            _ => Err(String::from("too many arguments")),
+        },
+    },
+    Command {
+        name: "fold",
+        usage: "fold",
+        help: "Fold or unfold the lines without checkpoint",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.toggle_folding()),
+    },
+    Command {
+        name: "fold-context",
+        usage: "fold-context N|+N|-N",
+        help: "Number of lines kept around instrumented lines when folding",
+        completion: Completion::Nothing,
+        run: fold_context,
+    },
+    Command {
+        name: "expand",
+        usage: "expand",
+        help: "Expand the fold under the cursor",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.expand_fold()),
+    },
+    Command {
+        name: "set",
+        usage: "set tabwidth=N|foldcontext=N",
+        help: "Change an option",
+        completion: Completion::Option,
+        run: set_option,
+    },
+    Command {
+        name: "cycle-tabwidth",
+        usage: "cycle-tabwidth",
+        help: "Switch between tab widths of 8, 4 and 2",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.cycle_tab_width()),
+    },
+    Command {
+        name: "next-panel",
+        usage: "next-panel",
+        help: "Select the next panel",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.layout.select_next()),
+    },
+    Command {
+        name: "toggle-panel",
+        usage: "toggle-panel checkpoints|addresses|source",
+        help: "Show or hide a panel",
+        completion: Completion::Panel,
+        run: |app, args| panel_arg(args).map(|kind| app.toggle_panel(kind)),
+    },
+    Command {
+        name: "grow",
+        usage: "grow",
+        help: "Widen the selected panel",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.resize_panel(1)),
+    },
+    Command {
+        name: "shrink",
+        usage: "shrink",
+        help: "Narrow the selected panel",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.resize_panel(-1)),
+    },
+    Command {
+        name: "move-panel",
+        usage: "move-panel left|right",
+        help: "Move the selected panel",
+        completion: Completion::Nothing,
+        run: move_panel,
+    },
+    Command {
+        name: "export",
+        usage: "export PATH",
+        help: "Write the displayed lines to a text file",
+        completion: Completion::Path,
+        run: |app, args| app.export_view(one_arg(args)?),
+    },
+    Command {
+        name: "open",
+        usage: "open PATH",
+        help: "Open another profile",
+        completion: Completion::Path,
+        run: |app, args| app.open(one_arg(args)?),
+    },
+    Command {
+        name: "next-profile",
+        usage: "next-profile",
+        help: "Open the next profile given on the command line",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).and_then(|_| app.switch_profile(1)),
+    },
+    Command {
+        name: "previous-profile",
+        usage: "previous-profile",
+        help: "Open the previous profile given on the command line",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).and_then(|_| app.switch_profile(-1)),
+    },
+    Command {
+        name: "prompt",
+        usage: "prompt",
+        help: "Open the command line",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.open_command_line()),
+    },
+    Command {
+        name: "suspend",
+        usage: "suspend",
+        help: "Suspend the application",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.should_suspend = true),
+    },
+    Command {
+        name: "stats",
+        usage: "stats",
+        help: "Show or hide the summary of the profile",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.toggle_stats()),
+    },
+    Command {
+        name: "help",
+        usage: "help [COMMAND]",
+        help: "Describe a command, or list the commands",
+        completion: Completion::Command,
+        run: |app, args| {
+            let message = match args {
+                [] => COMMANDS
+                    .iter()
+                    .map(|c| c.name)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                [name] => {
+                    let command = find(name)?;
+                    format!("{}: {}", command.usage, command.help)
+                }
+                _ => return Err(String::from("expected at most one argument")),
+            };
+
+            app.set_message(message);
+            Ok(())
+        },
+    },
+    Command {
+        name: "quit",
+        usage: "quit",
+        help: "Quit the application",
+        completion: Completion::Nothing,
+        run: |app, args| no_args(args).map(|_| app.quit()),
+    },
+];
+
+/// Find a command by its name, or by a prefix matching a single command
+pub fn find(name: &str) -> Result<&'static Command, String> {
+    if let Some(command) = COMMANDS.iter().find(|c| c.name == name) {
+        return Ok(command);
+    }
+
+    let candidates: Vec<&Command> = COMMANDS
+        .iter()
+        .filter(|c| c.name.starts_with(name))
+        .collect();
+
+    match candidates.as_slice() {
+        [command] => Ok(command),
+        [] => Err(format!("unknown command {:?}", name)),
+        _ => Err(format!(
+            "ambiguous command {:?}: {}",
+            name,
+            candidates
+                .iter()
+                .map(|c| c.name)
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Run a command line such as `goto 42`
+pub fn execute(app: &mut App, line: &str) -> Result<(), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let (name, args) = match words.split_first() {
+        Some((name, args)) => (name, args),
+        None => return Ok(()),
+    };
+
+    let command = find(name)?;
+
+    (command.run)(app, args)
+        .map_err(|e| format!("{}: {} (usage: {})", command.name, e, command.usage))
+}
+
+fn complete_command(word: &str) -> Vec<String> {
+    COMMANDS
+        .iter()
+        .map(|c| c.name.to_string())
+        .filter(|n| n.starts_with(word))
+        .collect()
+}
+
+fn complete_path(word: &str) -> Vec<String> {
+    let (dir, prefix) = match word.rfind('/') {
+        Some(i) => (&word[..=i], &word[i + 1..]),
+        None => ("", word),
+    };
+
+    let entries = match std::fs::read_dir(if dir.is_empty() { "." } else { dir }) {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    let mut candidates: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let is_dir = e.file_type().map(|t| t.is_dir()).unwrap_or(false);
+
+            match name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.')) {
+                true => Some(format!("{}{}{}", dir, name, if is_dir { "/" } else { "" })),
+                false => None,
+            }
+        })
+        .collect();
+
+    candidates.sort();
+    candidates
+}
+
+fn complete_checkpoint(app: &App, word: &str) -> Vec<String> {
+    app.profile
+        .checkpoints
+        .iter()
+        .flat_map(|(id, name)| vec![name.clone(), id.to_string()])
+        .filter(|c| c.starts_with(word))
+        .collect()
+}
+
+fn complete_filter(app: &App, word: &str) -> Vec<String> {
+    for prefix in ["cp:", "!cp:"].iter() {
+        if let Some(cp) = word.strip_prefix(prefix) {
+            return complete_checkpoint(app, cp)
+                .into_iter()
+                .map(|c| format!("{}{}", prefix, c))
+                .collect();
+        }
+    }
+
+    filter::TERMS
+        .iter()
+        .filter(|t| t.starts_with(word))
+        .map(|t| t.to_string())
+        .collect()
+}
+
+/// Candidates to replace the last word of `input`, and the position of this word
+pub fn complete(app: &App, input: &str) -> (usize, Vec<String>) {
+    let start = input.rfind(' ').map(|i| i + 1).unwrap_or(0);
+    let word = &input[start..];
+
+    if start == 0 {
+        return (start, complete_command(word));
+    }
+
+    let completion = input
+        .split_whitespace()
+        .next()
+        .and_then(|name| find(name).ok())
+        .map(|c| c.completion)
+        .unwrap_or(Completion::Nothing);
+
+    let candidates = match completion {
+        Completion::Nothing => vec![],
+        Completion::Checkpoint => complete_checkpoint(app, word),
+        Completion::Command => complete_command(word),
+        Completion::Filter => complete_filter(app, word),
+        Completion::Option => OPTIONS
+            .iter()
+            .filter(|o| o.starts_with(word))
+            .map(|o| o.to_string())
+            .collect(),
+        Completion::Panel => PanelKind::ALL
+            .iter()
+            .map(|k| k.name().to_string())
+            .filter(|n| n.starts_with(word))
+            .collect(),
+        Completion::Path => complete_path(word),
+    };
+
+    (start, candidates)
+}
+
+/// Text typed after `:`
+#[derive(Default)]
+pub struct CommandLine {
+    pub input: String,
+    /// Position in the history while browsing it
+    history_pos: Option<usize>,
+    /// Completions of the last word, and the one currently inserted
+    completions: Option<(usize, Vec<String>, usize)>,
+}
+
+impl CommandLine {
+    pub fn new() -> CommandLine {
+        CommandLine::default()
+    }
+
+    pub fn candidates(&self) -> &[String] {
+        match &self.completions {
+            Some((_, candidates, _)) => candidates,
+            None => &[],
+        }
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.completions = None;
+        self.input.push(c);
+    }
+
+    pub fn pop(&mut self) {
+        self.completions = None;
+        self.input.pop();
+    }
+
+    pub fn clear(&mut self) {
+        self.completions = None;
+        self.input.clear();
+    }
+
+    /// Insert the next completion of the last word
+    pub fn complete(&mut self, app: &App) {
+        let (start, candidates, index) = match self.completions.take() {
+            Some((start, candidates, index)) => {
+                let index = (index + 1) % candidates.len();
+                (start, candidates, index)
+            }
+            None => {
+                let (start, candidates) = complete(app, &self.input);
+
+                if candidates.is_empty() {
+                    return;
+                }
+
+                (start, candidates, 0)
+            }
+        };
+
+        self.input.truncate(start);
+        self.input.push_str(&candidates[index]);
+
+        // A single candidate is final, start a new word unless it expects a value
+        if candidates.len() == 1 {
+            if !self.input.ends_with(['/', '=', ':']) {
+                self.input.push(' ');
+            }
+        } else {
+            self.completions = Some((start, candidates, index));
+        }
+    }
+
+    /// Replace the input by an older (`-1`) or more recent (`1`) history entry
+    pub fn browse_history(&mut self, history: &[String], direction: isize) {
+        if history.is_empty() {
+            return;
+        }
+
+        let pos = match (self.history_pos, direction < 0) {
+            (None, true) => history.len() - 1,
+            (None, false) => return,
+            (Some(pos), true) => pos.saturating_sub(1),
+            (Some(pos), false) if pos + 1 < history.len() => pos + 1,
+            (Some(_), false) => {
+                self.history_pos = None;
+                self.clear();
+                return;
+            }
+        };
+
+        self.history_pos = Some(pos);
+        self.completions = None;
+        self.input = history[pos].clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{complete, execute, find, CommandLine};
+    use crate::config::Config;
+    use crate::controller::App;
+    use crate::model::profile::{Profile, ProfileItem};
+
+    fn current_line(app: &App) -> Option<usize> {
+        match app.get_current_item() {
+            Some(ProfileItem::Line(_, l)) => Some(l.nb),
+            _ => None,
+        }
+    }
+
+    fn app() -> App {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        App::new("profile", profile, &Config::default())
+    }
+
+    #[test]
+    fn find_commands() {
+        assert_eq!(find("quit").unwrap().name, "quit");
+        assert_eq!(find("q").unwrap().name, "quit");
+        assert!(find("fo").is_err());
+        assert!(find("nope").is_err());
+    }
+
+    #[test]
+    fn execute_commands() {
+        let mut app = app();
+
+        execute(&mut app, "set tabwidth=3").unwrap();
+        assert_eq!(app.tab_width, 3);
+
+        let e = execute(&mut app, "set tabwidth=x").unwrap_err();
+        assert!(e.starts_with("set: invalid value"));
+        assert!(execute(&mut app, "set tabwidth=0").is_err());
+        assert!(execute(&mut app, "set tabwidth=17").is_err());
+        assert_eq!(app.tab_width, 3);
+
+        execute(&mut app, "goto 13").unwrap();
+        assert_eq!(current_line(&app), Some(13));
+
+        execute(&mut app, "goto 0x108a30").unwrap();
+        assert_eq!(current_line(&app), Some(15));
+
+        execute(&mut app, "focus Before_hello").unwrap();
+        assert_eq!(app.focus, Some(1));
+    }
+
+    #[test]
+    fn huge_counts() {
+        let mut app = app();
+        let max = usize::MAX.to_string();
+
+        execute(&mut app, &format!("right {}", max)).unwrap();
+        execute(&mut app, &format!("left {}", max)).unwrap();
+        assert_eq!(app.h_offset, 0);
+
+        execute(&mut app, &format!("down {}", max)).unwrap();
+        assert_eq!(app.cursor, app.get_item_count() - 1);
+        execute(&mut app, &format!("up {}", max)).unwrap();
+        assert_eq!(app.cursor, 0);
+
+        execute(&mut app, "fold").unwrap();
+        execute(&mut app, &format!("set foldcontext={}", max)).unwrap();
+        execute(&mut app, &format!("fold-context +{}", isize::MAX)).unwrap();
+        assert_eq!(app.fold_context, usize::MAX);
+        execute(&mut app, &format!("fold-context -{}", isize::MAX)).unwrap();
+        assert_eq!(app.fold_context, usize::MAX - isize::MAX as usize);
+    }
+
+    #[test]
+    fn completion() {
+        let app = app();
+
+        assert_eq!(
+            complete(&app, "fo"),
+            (
+                0,
+                vec![
+                    String::from("focus"),
+                    String::from("fold"),
+                    String::from("fold-context")
+                ]
+            )
+        );
+        assert_eq!(
+            complete(&app, "focus B"),
+            (6, vec![String::from("Before_hello")])
+        );
+        assert_eq!(
+            complete(&app, "filter !cp:m"),
+            (7, vec![String::from("!cp:memviz_begin")])
+        );
+        assert_eq!(
+            complete(&app, "set t"),
+            (4, vec![String::from("tabwidth=")])
+        );
+        assert_eq!(
+            complete(&app, "open assets/test/hel"),
+            (5, vec![String::from("assets/test/hello/")])
+        );
+
+        let mut line = CommandLine::new();
+        line.input = String::from("toggle-panel s");
+        line.complete(&app);
+        assert_eq!(line.input, "toggle-panel source ");
+
+        line.input = String::from("fo");
+        line.complete(&app);
+        line.complete(&app);
+        assert_eq!(line.input, "fold");
+    }
+
+    #[test]
+    fn history() {
+        let history = vec![String::from("fold"), String::from("goto 3")];
+        let mut line = CommandLine::new();
+
+        line.browse_history(&history, -1);
+        assert_eq!(line.input, "goto 3");
+        line.browse_history(&history, -1);
+        line.browse_history(&history, -1);
+        assert_eq!(line.input, "fold");
+        line.browse_history(&history, 1);
+        line.browse_history(&history, 1);
+        assert_eq!(line.input, "");
+    }
+}
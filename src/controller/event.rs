@@ -0,0 +1,165 @@
+use std::{
+    io::stdin,
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver, RecvError, Sender},
+    thread,
+    time::Duration,
+};
+
+use crate::model::profile::Update;
+use signal_hook::{
+    consts::{SIGINT, SIGTERM, SIGTSTP, SIGWINCH},
+    iterator::Signals,
+};
+use termion::{
+    event::{self as term_event, Key, MouseEvent},
+    input::TermRead,
+};
+
+/// Messages sent to the application by worker threads
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    /// Text to display in the status bar
+    Status(String),
+    /// A watched file has been written or replaced
+    FileChanged(PathBuf),
+    /// An update of the profile received by the listener
+    Update(Update),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Event {
+    Key(Key),
+    Mouse(MouseEvent),
+    /// The terminal has been resized
+    Resize,
+    /// Periodic wake up, used to update time dependent display
+    Tick,
+    /// The application has been asked to stop (SIGTERM, SIGINT)
+    Terminate,
+    /// The application has been asked to suspend (SIGTSTP)
+    Suspend,
+    Message(Message),
+}
+
+/// Multiplexes the application events: key presses, mouse, signals, ticks and messages
+/// from worker threads. Each source runs on its own thread and feeds a single channel.
+pub struct Events {
+    tx: Sender<Event>,
+    rx: Receiver<Event>,
+}
+
+impl Events {
+    /// Listen to keyboard, mouse, signals and emit a tick every `tick_rate`
+    pub fn new(tick_rate: Duration) -> Events {
+        let events = Events::with_tick(tick_rate);
+
+        events.spawn_input();
+        events.spawn_signals();
+
+        events
+    }
+
+    /// Only emit ticks and worker messages
+    pub fn with_tick(tick_rate: Duration) -> Events {
+        let (tx, rx) = channel();
+        let events = Events { tx, rx };
+
+        let tx = events.sender();
+        thread::spawn(move || loop {
+            thread::sleep(tick_rate);
+
+            if tx.send(Event::Tick).is_err() {
+                return;
+            }
+        });
+
+        events
+    }
+
+    fn spawn_input(&self) {
+        let tx = self.sender();
+
+        thread::spawn(move || {
+            for event in stdin().events() {
+                let event = match event {
+                    Ok(term_event::Event::Key(key)) => Event::Key(key),
+                    Ok(term_event::Event::Mouse(mouse)) => Event::Mouse(mouse),
+                    Ok(term_event::Event::Unsupported(_)) => continue,
+                    Err(_) => return,
+                };
+
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    fn spawn_signals(&self) {
+        let tx = self.sender();
+
+        // Without signal handling, the terminal size is still checked at each draw
+        if let Ok(mut signals) = Signals::new([SIGWINCH, SIGTERM, SIGINT, SIGTSTP]) {
+            thread::spawn(move || {
+                for signal in signals.forever() {
+                    let event = match signal {
+                        SIGWINCH => Event::Resize,
+                        SIGTSTP => Event::Suspend,
+                        _ => Event::Terminate,
+                    };
+
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
+    }
+
+    /// A sender worker threads can use to post messages
+    pub fn sender(&self) -> Sender<Event> {
+        self.tx.clone()
+    }
+
+    /// Block until the next event
+    pub fn next(&self) -> Result<Event, RecvError> {
+        self.rx.recv()
+    }
+
+    /// Next event, if one is already pending
+    pub fn try_next(&self) -> Option<Event> {
+        self.rx.try_recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Event, Events, Message};
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn worker_messages_and_ticks() {
+        let events = Events::with_tick(Duration::from_millis(10));
+        let tx = events.sender();
+
+        thread::spawn(move || {
+            tx.send(Event::Message(Message::Status(String::from("done"))))
+                .unwrap();
+        });
+
+        let mut got_message = false;
+        let mut got_tick = false;
+
+        while !(got_message && got_tick) {
+            match events.next().unwrap() {
+                Event::Message(Message::Status(s)) => {
+                    assert_eq!(s, "done");
+                    got_message = true;
+                }
+                Event::Tick => got_tick = true,
+                e => panic!("unexpected event {:?}", e),
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::model::profile::{Profile, ProfileItem};
+
+use super::fold::Row;
+
+/// Prefixes of the filter terms, for completion
+pub const TERMS: [&str; 5] = ["cp:", "!cp:", "file:", "fn:", "met"];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Term {
+    /// The line met the checkpoint
+    Checkpoint(u32),
+    /// The path of the file contains the text
+    File(String),
+    /// The name of the function contains the text
+    Function(String),
+    /// The line met at least one checkpoint
+    Met,
+}
+
+/// Lines to display, described by space separated terms that must all match:
+/// `cp:ID` or `cp:NAME`, `file:TEXT`, `fn:TEXT` and `met`. A term starting with `!`
+/// is negated.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Filter {
+    pub expression: String,
+    terms: Vec<(bool, Term)>,
+}
+
+impl Filter {
+    pub fn parse(expression: &str, profile: &Profile) -> Result<Filter, String> {
+        let mut terms = vec![];
+
+        for word in expression.split_whitespace() {
+            let (negated, word) = match word.strip_prefix('!') {
+                Some(word) => (true, word),
+                None => (false, word),
+            };
+
+            let term = if let Some(cp) = word.strip_prefix("cp:") {
+                Term::Checkpoint(profile.checkpoint_id(cp)?)
+            } else if let Some(file) = word.strip_prefix("file:") {
+                Term::File(String::from(file))
+            } else if let Some(function) = word.strip_prefix("fn:") {
+                Term::Function(String::from(function))
+            } else if word == "met" {
+                Term::Met
+            } else {
+                return Err(format!(
+                    "invalid filter term {:?}, expected cp:, file:, fn: or met",
+                    word
+                ));
+            };
+
+            terms.push((negated, term));
+        }
+
+        if terms.is_empty() {
+            return Err(String::from("empty filter"));
+        }
+
+        Ok(Filter {
+            expression: String::from(expression.trim()),
+            terms,
+        })
+    }
+
+    fn matches(&self, item: &ProfileItem) -> bool {
+        let (f, l) = match item {
+            ProfileItem::File(_) => return false,
+            ProfileItem::Line(f, l) => (f, l),
+        };
+
+        self.terms.iter().all(|(negated, term)| {
+            let matched = match term {
+                Term::Checkpoint(id) => l.checkpoints.contains(id),
+                Term::File(text) => f.borrow().path.expand().to_string_lossy().contains(text),
+                Term::Function(text) => l.function.as_ref().is_some_and(|f| f.contains(text)),
+                Term::Met => !l.checkpoints.is_empty(),
+            };
+
+            matched != *negated
+        })
+    }
+
+    /// Matching lines, under the header of their file
+    pub fn rows(&self, items: &[ProfileItem]) -> Vec<Row> {
+        let mut rows = vec![];
+        let mut header = None;
+
+        for (i, item) in items.iter().enumerate() {
+            if item.is_file() {
+                header = Some(i);
+            } else if self.matches(item) {
+                if let Some(h) = header.take() {
+                    rows.push(Row::Item(h));
+                }
+
+                rows.push(Row::Item(i));
+            }
+        }
+
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Filter;
+    use crate::controller::fold::Row;
+    use crate::model::profile::Profile;
+
+    #[test]
+    fn filter_rows() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let items: Vec<_> = profile.items.iter().cloned().collect();
+
+        let filter = Filter::parse("cp:Before_hello !cp:0", &profile).unwrap();
+        assert_eq!(
+            filter.rows(&items),
+            vec![Row::Item(0), Row::Item(4), Row::Item(5)]
+        );
+
+        let filter = Filter::parse("fn:nope", &profile).unwrap();
+        assert_eq!(filter.rows(&items), vec![]);
+
+        assert!(Filter::parse("cp:unknown", &profile).is_err());
+        assert!(Filter::parse("lines", &profile).is_err());
+    }
+}
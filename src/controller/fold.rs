@@ -0,0 +1,165 @@
+use crate::model::profile::ProfileItem;
+
+use std::collections::BTreeSet;
+
+/// A displayed row.
+/// Rows refer to profile items by their index in `App::items`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Row {
+    Item(usize),
+    /// A run of `len` non-instrumented lines starting at item `start`
+    Fold {
+        start: usize,
+        len: usize,
+    },
+}
+
+impl Row {
+    pub fn contains(&self, index: usize) -> bool {
+        match *self {
+            Row::Item(i) => i == index,
+            Row::Fold { start, len } => (start..start + len).contains(&index),
+        }
+    }
+
+    pub fn first_item(&self) -> usize {
+        match *self {
+            Row::Item(i) => i,
+            Row::Fold { start, .. } => start,
+        }
+    }
+}
+
+/// A line is instrumented if it has been met in at least one checkpoint
+/// or if it comes with an address range.
+pub fn is_instrumented(item: &ProfileItem) -> bool {
+    match item {
+        ProfileItem::File(_) => false,
+        ProfileItem::Line(_, l) => l.addr_range != (0, 0) || !l.checkpoints.is_empty(),
+    }
+}
+
+/// One row per item, nothing folded.
+pub fn unfolded(items: &[ProfileItem]) -> Vec<Row> {
+    (0..items.len()).map(Row::Item).collect()
+}
+
+/// Fold runs of non-instrumented lines, keeping `context` lines around instrumented ones.
+/// File headers are never folded, and a run is only folded if it hides at least two lines.
+/// Runs whose first item belongs to `expanded` are left unfolded.
+pub fn folded(items: &[ProfileItem], context: usize, expanded: &BTreeSet<usize>) -> Vec<Row> {
+    let mut keep: Vec<bool> = items.iter().map(|item| item.is_file()).collect();
+
+    for (i, item) in items.iter().enumerate() {
+        if !is_instrumented(item) {
+            continue;
+        }
+
+        let first = i.saturating_sub(context);
+        let last = usize::min(i.saturating_add(context), items.len() - 1);
+
+        for j in first..=last {
+            if items[j].is_in_same_file(item) {
+                keep[j] = true;
+            }
+        }
+    }
+
+    let mut rows = vec![];
+    let mut i = 0;
+
+    while i < items.len() {
+        if keep[i] {
+            rows.push(Row::Item(i));
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+
+        while i < items.len() && !keep[i] {
+            i += 1;
+        }
+
+        let len = i - start;
+
+        if len < 2 || expanded.contains(&start) {
+            rows.extend((start..i).map(Row::Item));
+        } else {
+            rows.push(Row::Fold { start, len });
+        }
+    }
+
+    rows
+}
+
+/// Index of the row displaying the item at `index`.
+pub fn row_of_item(rows: &[Row], index: usize) -> usize {
+    rows.iter().position(|r| r.contains(index)).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{folded, row_of_item, Row};
+    use crate::model::profile::{FileInfo, LineInfo, PathInfo, ProfileItem};
+    use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
+
+    fn items(instrumented: &[usize], count: usize) -> Vec<ProfileItem> {
+        let path = PathInfo::new("".to_string(), "foo.c".to_string());
+        let f = Rc::new(RefCell::new(FileInfo::new(path)));
+
+        let mut ret = vec![ProfileItem::File(f.clone())];
+
+        for nb in 1..=count {
+            let checkpoints = if instrumented.contains(&nb) {
+                bt_set!(0)
+            } else {
+                bt_set!()
+            };
+
+            ret.push(ProfileItem::Line(
+                f.clone(),
+                LineInfo::new(nb, (0, 0), Some(String::new()), None, true, checkpoints),
+            ));
+        }
+
+        ret
+    }
+
+    #[test]
+    fn fold_with_context() {
+        let items = items(&[5], 12);
+        let rows = folded(&items, 1, &BTreeSet::new());
+
+        assert_eq!(
+            rows,
+            vec![
+                Row::Item(0),
+                Row::Fold { start: 1, len: 3 },
+                Row::Item(4),
+                Row::Item(5),
+                Row::Item(6),
+                Row::Fold { start: 7, len: 6 },
+            ]
+        );
+        assert_eq!(row_of_item(&rows, 9), 5);
+    }
+
+    #[test]
+    fn fold_keeps_single_lines() {
+        let items = items(&[3], 5);
+        let rows = folded(&items, 1, &BTreeSet::new());
+
+        assert_eq!(rows, (0..6).map(Row::Item).collect::<Vec<Row>>());
+    }
+
+    #[test]
+    fn fold_expanded() {
+        let items = items(&[5], 12);
+        let rows = folded(&items, 1, &bt_set!(7));
+
+        assert_eq!(rows.len(), 11);
+        assert_eq!(rows[1], Row::Fold { start: 1, len: 3 });
+        assert_eq!(rows[10], Row::Item(12));
+    }
+}
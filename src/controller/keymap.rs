@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use termion::event::Key;
+
+use super::command::find;
+
+/// Commands run by the keys, see `command::COMMANDS`
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: HashMap<Key, String>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = [
+            (Key::Up, "up"),
+            (Key::Down, "down"),
+            (Key::PageUp, "page-up"),
+            (Key::PageDown, "page-down"),
+            (Key::Left, "left"),
+            (Key::Right, "right"),
+            (Key::Home, "home"),
+            (Key::Char('t'), "cycle-tabwidth"),
+            (Key::Char('\t'), "next-panel"),
+            (Key::Char('<'), "shrink"),
+            (Key::Char('>'), "grow"),
+            (Key::Char('['), "move-panel left"),
+            (Key::Char(']'), "move-panel right"),
+            (Key::Char('1'), "toggle-panel checkpoints"),
+            (Key::Char('2'), "toggle-panel addresses"),
+            (Key::Char('3'), "toggle-panel source"),
+            (Key::Char('\n'), "expand"),
+            (Key::Char('z'), "fold"),
+            (Key::Char('+'), "fold-context +1"),
+            (Key::Char('-'), "fold-context -1"),
+            (Key::Char('n'), "next-profile"),
+            (Key::Char('p'), "previous-profile"),
+            (Key::Char('s'), "stats"),
+            (Key::Char(':'), "prompt"),
+            (Key::Esc, "focus"),
+            (Key::Char('q'), "quit"),
+            (Key::Ctrl('c'), "quit"),
+            (Key::Ctrl('z'), "suspend"),
+        ];
+
+        Keymap {
+            bindings: bindings
+                .iter()
+                .map(|(key, command)| (*key, command.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// Parse a key name: a character, `space`, `enter`, `tab`, `esc`, `backspace`,
+/// `delete`, `insert`, the arrows, `home`, `end`, `page-up`, `page-down`, `f1` to `f12`,
+/// or `ctrl-` and `alt-` followed by a character
+pub fn parse_key(name: &str) -> Result<Key, String> {
+    let single = |s: &str| {
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+
+    let key = match name {
+        "space" => Key::Char(' '),
+        "enter" => Key::Char('\n'),
+        "tab" => Key::Char('\t'),
+        "esc" => Key::Esc,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "insert" => Key::Insert,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "page-up" => Key::PageUp,
+        "page-down" => Key::PageDown,
+        _ => {
+            if let Some(c) = single(name) {
+                Key::Char(c)
+            } else if let Some(c) = name.strip_prefix("ctrl-").and_then(single) {
+                Key::Ctrl(c)
+            } else if let Some(c) = name.strip_prefix("alt-").and_then(single) {
+                Key::Alt(c)
+            } else if let Some(n) = name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                match n {
+                    1..=12 => Key::F(n),
+                    _ => return Err(format!("unknown key {:?}", name)),
+                }
+            } else {
+                return Err(format!("unknown key {:?}", name));
+            }
+        }
+    };
+
+    Ok(key)
+}
+
+impl Keymap {
+    pub fn get(&self, key: Key) -> Option<&str> {
+        self.bindings.get(&key).map(|c| c.as_str())
+    }
+
+    /// Bind a key to a command line, or unbind it if the command line is empty
+    pub fn bind(&mut self, key: &str, command: &str) -> Result<(), String> {
+        let key = parse_key(key)?;
+
+        match command.split_whitespace().next() {
+            None => {
+                self.bindings.remove(&key);
+            }
+            Some(name) => {
+                find(name)?;
+                self.bindings.insert(key, String::from(command.trim()));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_key, Keymap};
+    use termion::event::Key;
+
+    #[test]
+    fn bind_keys() {
+        assert_eq!(parse_key("j"), Ok(Key::Char('j')));
+        assert_eq!(parse_key("ctrl-d"), Ok(Key::Ctrl('d')));
+        assert_eq!(parse_key("page-down"), Ok(Key::PageDown));
+        assert_eq!(parse_key("f5"), Ok(Key::F(5)));
+        assert!(parse_key("f13").is_err());
+        assert!(parse_key("hyper-x").is_err());
+
+        let mut keymap = Keymap::default();
+        keymap.bind("j", "down").unwrap();
+        keymap.bind("q", "").unwrap();
+        assert_eq!(keymap.get(Key::Char('j')), Some("down"));
+        assert_eq!(keymap.get(Key::Char('q')), None);
+        assert!(keymap.bind("x", "explode").is_err());
+    }
+}
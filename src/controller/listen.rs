@@ -0,0 +1,144 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+};
+
+use super::event::{Event, Message};
+use crate::model::profile::StreamDecoder;
+
+/// Source of profile updates. A socket is removed when the listener is dropped.
+pub struct Listener {
+    socket: Option<PathBuf>,
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        if let Some(path) = &self.socket {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Decode the lines of a stream and post them as `Message::Update`.
+/// Returns false once the application stopped listening.
+fn read_stream<R: BufRead>(name: &str, reader: R, tx: &Sender<Event>) -> bool {
+    let mut decoder = StreamDecoder::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+
+        let message = match decoder.decode(&line) {
+            Ok(Some(update)) => Message::Update(update),
+            Ok(None) => continue,
+            Err(e) => Message::Status(format!("{}:{}: {}", name, i + 1, e)),
+        };
+
+        if tx.send(Event::Message(message)).is_err() {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Read updates from a FIFO, one writer after the other
+fn listen_fifo(path: PathBuf, tx: Sender<Event>) {
+    let name = path.display().to_string();
+
+    thread::spawn(move || loop {
+        // Blocks until a writer opens the FIFO
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(e) => {
+                let _ = tx.send(Event::Message(Message::Status(format!("{}: {}", name, e))));
+                return;
+            }
+        };
+
+        if !read_stream(&name, BufReader::new(file), &tx) {
+            return;
+        }
+    });
+}
+
+/// Accept connections on a Unix socket, each client getting its own decoder
+fn listen_socket(listener: UnixListener, name: String, tx: Sender<Event>) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream: UnixStream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+
+            let tx = tx.clone();
+            let name = name.clone();
+
+            thread::spawn(move || read_stream(&name, BufReader::new(stream), &tx));
+        }
+    });
+}
+
+/// Listen to profile updates on `path`. If `path` is a FIFO, it is read; otherwise a
+/// Unix socket is created there, replacing a stale one.
+pub fn listen<P: AsRef<Path>>(path: P, tx: Sender<Event>) -> io::Result<Listener> {
+    let path = path.as_ref().to_path_buf();
+
+    match fs::metadata(&path) {
+        Ok(metadata) if metadata.file_type().is_fifo() => {
+            listen_fifo(path, tx);
+            return Ok(Listener { socket: None });
+        }
+        Ok(metadata) if metadata.file_type().is_socket() => fs::remove_file(&path)?,
+        _ => {}
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    listen_socket(listener, path.display().to_string(), tx);
+
+    Ok(Listener { socket: Some(path) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::listen;
+    use crate::controller::event::{Event, Message};
+    use std::{io::Write, os::unix::net::UnixStream, sync::mpsc::channel, time::Duration};
+
+    #[test]
+    fn socket_updates() {
+        let path =
+            std::env::temp_dir().join(format!("phaseviz-listen-{}.sock", std::process::id()));
+
+        let (tx, rx) = channel();
+        let listener = listen(&path, tx).unwrap();
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client
+            .write_all(b"0 -> begin\n13 [0x1;0x2] -> 0\nfl=main.c\n13 [0x1;0x2] -> 0\n")
+            .unwrap();
+
+        let next = || rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        assert!(matches!(next(), Event::Message(Message::Update(_))));
+        match next() {
+            Event::Message(Message::Status(s)) => {
+                assert!(s.ends_with(":2: code location before any fl= line"))
+            }
+            e => panic!("unexpected event {:?}", e),
+        }
+        assert!(matches!(next(), Event::Message(Message::Update(_))));
+
+        drop(listener);
+        assert!(!path.exists());
+    }
+}
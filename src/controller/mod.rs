@@ -0,0 +1,1164 @@
+use crate::config::Config;
+use crate::model::profile::{LineKey, Profile, ProfileItem};
+use crate::model::stats::Stats;
+use crate::ui::layout::{PanelArea, PanelKind, PanelLayout, PanelSize};
+use crate::ui::theme::Theme;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use termion::event::{Key, MouseButton, MouseEvent};
+
+use tui::backend::TermionBackend;
+use tui::Terminal;
+
+pub mod command;
+pub mod event;
+pub mod filter;
+pub mod fold;
+pub mod keymap;
+pub mod listen;
+pub mod terminal;
+pub mod watch;
+
+use command::CommandLine;
+use event::{Event, Events, Message};
+use filter::Filter;
+use fold::Row;
+use keymap::Keymap;
+use terminal::TerminalGuard;
+
+pub const DEFAULT_FOLD_CONTEXT: usize = 3;
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+/// Accepted tab widths
+const MAX_TAB_WIDTH: usize = 16;
+const H_SCROLL_STEP: usize = 4;
+const WHEEL_STEP: usize = 3;
+const TICK_RATE: Duration = Duration::from_millis(250);
+const MESSAGE_DURATION: Duration = Duration::from_secs(3);
+const HISTORY_SIZE: usize = 100;
+/// Writes to the profile are coalesced until it has not changed for this long
+const RELOAD_DELAY: Duration = Duration::from_millis(200);
+const FLASH_DURATION: Duration = Duration::from_secs(2);
+
+/// Check a tab width given in the config file or on the command line
+pub fn check_tab_width(tab_width: usize) -> Result<(), String> {
+    match tab_width {
+        1..=MAX_TAB_WIDTH => Ok(()),
+        _ => Err(format!(
+            "the tab width must be between 1 and {}, not {}",
+            MAX_TAB_WIDTH, tab_width
+        )),
+    }
+}
+
+pub struct App {
+    pub profile_path: PathBuf,
+    /// Profiles given on the command line
+    profiles: Vec<PathBuf>,
+    /// Profile synced with the source files
+    pub profile: Profile,
+    /// Profile as parsed or received, before syncing
+    source: Profile,
+    pub items: Vec<ProfileItem>,
+    pub rows: Vec<Row>,
+    pub tab_width: usize,
+    pub h_offset: usize,
+    pub layout: PanelLayout,
+    /// Panels as drawn on screen
+    pub areas: Vec<PanelArea>,
+    /// Checkpoint whose lines are highlighted
+    pub focus: Option<u32>,
+    /// Panel being resized by dragging its border, and whether the border is on its right
+    drag: Option<(PanelKind, bool)>,
+    pub filter: Option<Filter>,
+    /// Summary of the profile, shown instead of the panels
+    pub stats: Option<Stats>,
+    /// First line of the summary on screen
+    pub stats_offset: usize,
+    /// Number of lines of the summary on screen, scrolled by a page
+    pub stats_height: usize,
+    keymap: Keymap,
+    pub theme: Theme,
+    /// Directories where to look for missing source files
+    search_paths: Vec<PathBuf>,
+    command_line: Option<CommandLine>,
+    history: Vec<String>,
+    folding: bool,
+    fold_context: usize,
+    expanded_folds: BTreeSet<usize>,
+    cursor: usize,
+    y_pos: usize,
+    height: u16,
+    /// Transient message, when it was set and whether it is an error
+    message: Option<(String, Instant, bool)>,
+    watch: bool,
+    listen: bool,
+    reload_pending: Option<Instant>,
+    resync_pending: bool,
+    /// Items whose checkpoints changed during the last update
+    flashed: BTreeSet<usize>,
+    flash_start: Option<Instant>,
+    should_suspend: bool,
+    should_quit: bool,
+}
+
+impl App {
+    fn move_up(&mut self, n: usize) {
+        self.y_pos = self.get_y_pos();
+        self.cursor = self.cursor.saturating_sub(n);
+
+        if self.cursor < self.y_pos {
+            self.y_pos = self.cursor;
+        }
+    }
+
+    fn move_down(&mut self, n: usize) {
+        if self.get_item_count() == 0 {
+            return;
+        }
+
+        self.y_pos = self.get_y_pos();
+        self.cursor = usize::min(self.cursor.saturating_add(n), self.get_item_count() - 1);
+
+        if self.cursor >= self.y_pos + self.height as usize {
+            self.y_pos = self.cursor + 1 - self.height as usize;
+        }
+    }
+
+    /// Move the view without moving the cursor, unless it would go out of the view
+    fn scroll(&mut self, n: isize) {
+        let max = self.get_item_count().saturating_sub(self.height as usize);
+        let y_pos = self.get_y_pos() as isize + n;
+
+        self.y_pos = usize::min(y_pos.max(0) as usize, max);
+
+        let last = self.y_pos + (self.height as usize).saturating_sub(1);
+        self.cursor = self.cursor.clamp(self.y_pos, usize::max(last, self.y_pos));
+        self.cursor = usize::min(self.cursor, self.get_item_count().saturating_sub(1));
+    }
+
+    /// Rebuild the displayed rows, keeping the cursor on the same item.
+    fn refresh_rows(&mut self) {
+        let current = self
+            .rows
+            .get(self.cursor)
+            .map(|r| r.first_item())
+            .unwrap_or(0);
+
+        self.refresh_rows_at(current);
+    }
+
+    /// Rebuild the displayed rows, moving the cursor to `current` item while keeping
+    /// its position on screen.
+    fn refresh_rows_at(&mut self, current: usize) {
+        let offset = self.get_cursor_offset();
+
+        // Filtered lines are not folded
+        self.rows = match &self.filter {
+            Some(filter) => filter.rows(&self.items),
+            None if self.folding => {
+                fold::folded(&self.items, self.fold_context, &self.expanded_folds)
+            }
+            None => fold::unfolded(&self.items),
+        };
+
+        self.cursor = fold::row_of_item(&self.rows, current);
+        self.y_pos = self.cursor.saturating_sub(offset);
+    }
+
+    fn toggle_folding(&mut self) {
+        self.folding = !self.folding;
+        self.expanded_folds.clear();
+        self.refresh_rows();
+    }
+
+    fn set_fold_context(&mut self, context: usize) {
+        if context != self.fold_context {
+            self.fold_context = context;
+
+            if self.folding {
+                self.expanded_folds.clear();
+                self.refresh_rows();
+            }
+
+            self.set_message(format!("Fold context: {} lines", context));
+        }
+    }
+
+    /// Only display the lines matching `expression`, or every line if it is empty
+    fn set_filter(&mut self, expression: &str) -> Result<(), String> {
+        self.filter = match expression.trim() {
+            "" => None,
+            expression => Some(Filter::parse(expression, &self.profile)?),
+        };
+
+        self.refresh_rows();
+
+        if self.filter.is_some() {
+            let count = self.rows.iter().filter(|r| !self.is_file_row(r)).count();
+            self.set_message(format!("{} matching lines", count));
+        }
+
+        Ok(())
+    }
+
+    fn is_file_row(&self, row: &Row) -> bool {
+        matches!(row, Row::Item(i) if self.items[*i].is_file())
+    }
+
+    /// Move the cursor to the row of an item, in the middle of the screen
+    fn move_to_item(&mut self, index: usize) {
+        self.cursor = fold::row_of_item(&self.rows, index);
+        self.y_pos = self.cursor.saturating_sub(self.height as usize / 2);
+    }
+
+    /// Go to a line of the current file, or to the line containing an address
+    fn goto(&mut self, target: &str) -> Result<(), String> {
+        let index = if let Some(hex) = target.strip_prefix("0x") {
+            let addr = u64::from_str_radix(hex, 16)
+                .map_err(|_| format!("invalid address {:?}", target))?;
+
+            self.items
+                .iter()
+                .position(|item| match item {
+                    ProfileItem::Line(_, l) => {
+                        l.addr_range != (0, 0) && l.addr_range.0 <= addr && addr <= l.addr_range.1
+                    }
+                    _ => false,
+                })
+                .ok_or_else(|| format!("no line at address {}", target))?
+        } else {
+            let nb: usize = target
+                .parse()
+                .map_err(|_| format!("invalid line {:?}", target))?;
+            let current = match self.get_current_item() {
+                Some(item) => item.clone(),
+                None => return Err(String::from("no current file")),
+            };
+
+            self.items
+                .iter()
+                .position(|item| match item {
+                    ProfileItem::Line(_, l) => l.nb == nb && item.is_in_same_file(&current),
+                    _ => false,
+                })
+                .ok_or_else(|| format!("no line {} in this file", nb))?
+        };
+
+        if !self.rows.iter().any(|r| r.contains(index)) {
+            return Err(String::from("the line is hidden by the filter"));
+        }
+
+        self.move_to_item(index);
+        Ok(())
+    }
+
+    /// Write the displayed rows to a text file
+    fn export_view(&mut self, path: &str) -> Result<(), String> {
+        let text = crate::ui::plain_view(self);
+
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path, e))?;
+        self.set_message(format!("View written to {}", path));
+
+        Ok(())
+    }
+
+    /// Open the next profile given on the command line, or the previous one
+    fn switch_profile(&mut self, step: isize) -> Result<(), String> {
+        if self.profiles.len() < 2 {
+            return Err(String::from("only one profile was given"));
+        }
+
+        let len = self.profiles.len() as isize;
+        let current = self
+            .profiles
+            .iter()
+            .position(|p| *p == self.profile_path)
+            .unwrap_or(0) as isize;
+        let path = self.profiles[(current + step).rem_euclid(len) as usize].clone();
+
+        self.open(&path.to_string_lossy())
+    }
+
+    /// Replace the profile by another one
+    fn open(&mut self, path: &str) -> Result<(), String> {
+        let profile = Profile::try_parse(path).map_err(|e| {
+            let e = e.to_string();
+            let e: Vec<&str> = e.split_whitespace().collect();
+            format!("{}: {}", path, e.join(" "))
+        })?;
+
+        self.profile_path = PathBuf::from(path);
+        self.source = profile;
+        self.filter = None;
+        self.focus = None;
+        self.resync();
+        self.flashed.clear();
+        self.move_to_item(0);
+        self.set_message(format!("Opened {}", path));
+
+        Ok(())
+    }
+
+    fn open_command_line(&mut self) {
+        self.command_line = Some(CommandLine::new());
+    }
+
+    pub fn get_command_line(&self) -> Option<&CommandLine> {
+        self.command_line.as_ref()
+    }
+
+    /// Run a command, reporting errors in the status bar
+    pub fn execute(&mut self, line: &str) {
+        if let Err(e) = command::execute(self, line) {
+            self.set_error(e);
+        }
+    }
+
+    fn handle_command_key(&mut self, key: Key) {
+        let mut line = match self.command_line.take() {
+            Some(line) => line,
+            None => return,
+        };
+
+        match key {
+            Key::Char('\n') => {
+                let input = line.input.trim().to_string();
+
+                if !input.is_empty() {
+                    self.history.retain(|h| *h != input);
+                    self.history.push(input.clone());
+
+                    if self.history.len() > HISTORY_SIZE {
+                        self.history.remove(0);
+                    }
+                }
+
+                self.execute(&input);
+                return;
+            }
+            Key::Esc | Key::Ctrl('c') => return,
+            Key::Backspace if line.input.is_empty() => return,
+            Key::Backspace => line.pop(),
+            Key::Ctrl('u') => line.clear(),
+            Key::Char('\t') => line.complete(self),
+            Key::Up => line.browse_history(&self.history, -1),
+            Key::Down => line.browse_history(&self.history, 1),
+            Key::Char(c) => line.push(c),
+            _ => {}
+        }
+
+        self.command_line = Some(line);
+    }
+
+    fn expand_fold(&mut self) {
+        if let Some(Row::Fold { start, len }) = self.rows.get(self.cursor).cloned() {
+            self.expanded_folds.insert(start);
+            self.refresh_rows();
+            self.set_message(format!("Expanded {} lines", len));
+        }
+    }
+
+    /// Display width of the longest source line
+    fn max_line_width(&self) -> usize {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                ProfileItem::Line(_, l) => l.line_content.as_ref(),
+                _ => None,
+            })
+            .map(|content| crate::ui::display_width(content, self.tab_width))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn scroll_left(&mut self, n: usize) {
+        self.h_offset = self.h_offset.saturating_sub(n);
+    }
+
+    fn scroll_right(&mut self, n: usize) {
+        let max = self.max_line_width().saturating_sub(1);
+        self.h_offset = usize::min(self.h_offset.saturating_add(n), max);
+    }
+
+    pub fn set_tab_width(&mut self, tab_width: usize) {
+        self.tab_width = tab_width;
+        self.set_message(format!("Tab width: {}", tab_width));
+    }
+
+    fn cycle_tab_width(&mut self) {
+        self.set_tab_width(match self.tab_width {
+            8 => 4,
+            4 => 2,
+            _ => 8,
+        });
+    }
+
+    fn toggle_panel(&mut self, kind: PanelKind) {
+        if !self.layout.toggle(kind) {
+            self.set_message(String::from("The last visible panel cannot be hidden"));
+        }
+    }
+
+    fn resize_panel(&mut self, steps: i16) {
+        if !self.layout.resize_selected(steps) {
+            self.set_message(String::from(
+                "This panel fills the remaining space, resize the other ones",
+            ));
+        }
+    }
+
+    /// Highlight the lines that met a checkpoint, or stop highlighting
+    pub fn set_focus(&mut self, focus: Option<u32>) {
+        self.focus = focus;
+
+        if let Some(id) = focus {
+            let name = match self.profile.checkpoint_name(id) {
+                Some(name) => format!("{}:{}", id, name),
+                None => id.to_string(),
+            };
+            self.set_message(format!("Focus on checkpoint {}", name));
+        }
+    }
+
+    fn toggle_focus(&mut self, id: u32) {
+        if self.focus == Some(id) {
+            self.set_focus(None);
+        } else {
+            self.set_focus(Some(id));
+        }
+    }
+
+    fn area_at(&self, x: u16, y: u16) -> Option<&PanelArea> {
+        self.areas
+            .iter()
+            .find(|a| x >= a.rect.x && x < a.rect.right() && y >= a.rect.y && y < a.rect.bottom())
+    }
+
+    /// Panel resized when dragging the border at column `x`: the panel on the left,
+    /// unless it fills the remaining space.
+    fn border_at(&self, x: u16) -> Option<(PanelKind, bool)> {
+        let i = self
+            .areas
+            .windows(2)
+            .position(|w| x + 1 >= w[0].rect.right() && x <= w[1].rect.x)?;
+
+        let resizable = |a: &PanelArea| self.layout.size(a.panel) != Some(PanelSize::Fill);
+
+        if resizable(&self.areas[i]) {
+            Some((self.areas[i].panel, true))
+        } else if resizable(&self.areas[i + 1]) {
+            Some((self.areas[i + 1].panel, false))
+        } else {
+            None
+        }
+    }
+
+    fn drag_border(&mut self, x: u16) {
+        let (kind, on_right) = match self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+
+        let (first, last) = match (self.areas.first(), self.areas.last()) {
+            (Some(first), Some(last)) => (first.rect.x, last.rect.right()),
+            _ => return,
+        };
+
+        let area = match self.areas.iter().find(|a| a.panel == kind) {
+            Some(area) => area,
+            None => return,
+        };
+
+        let width = if on_right {
+            x.saturating_sub(area.rect.x) + 1
+        } else {
+            area.rect.right().saturating_sub(x)
+        };
+
+        self.layout.resize_to(kind, width, last - first);
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        if self.stats.is_some() {
+            match event {
+                MouseEvent::Press(MouseButton::WheelUp, _, _) => {
+                    self.stats_offset = self.stats_offset.saturating_sub(WHEEL_STEP)
+                }
+                MouseEvent::Press(MouseButton::WheelDown, _, _) => self.stats_offset += WHEEL_STEP,
+                _ => {}
+            }
+            return;
+        }
+
+        match event {
+            MouseEvent::Press(MouseButton::WheelUp, _, _) => self.scroll(-(WHEEL_STEP as isize)),
+            MouseEvent::Press(MouseButton::WheelDown, _, _) => self.scroll(WHEEL_STEP as isize),
+            MouseEvent::Press(MouseButton::Left, x, y) => {
+                // Terminal coordinates start at 1
+                let (x, y) = (x.saturating_sub(1), y.saturating_sub(1));
+
+                if let Some(drag) = self.border_at(x) {
+                    self.drag = Some(drag);
+                    return;
+                }
+
+                let area = match self.area_at(x, y) {
+                    Some(area) => area.clone(),
+                    None => return,
+                };
+
+                self.layout.select(area.panel);
+
+                if let Some((id, _)) = area
+                    .header_cells
+                    .iter()
+                    .find(|(_, cell)| x >= cell.x && x < cell.right() && y == cell.y)
+                {
+                    self.toggle_focus(*id);
+                } else if y >= area.body.y && y < area.body.bottom() {
+                    let row = self.get_y_pos() + (y - area.body.y) as usize;
+
+                    if row < self.get_item_count() {
+                        self.cursor = row;
+                        self.y_pos = self.get_y_pos();
+                    }
+                }
+            }
+            MouseEvent::Hold(x, _) => self.drag_border(x.saturating_sub(1)),
+            MouseEvent::Release(_, _) => self.drag = None,
+            _ => {}
+        }
+    }
+
+    /// Display a transient message in the status bar
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some((message, Instant::now(), false));
+    }
+
+    /// Display an error in the status bar
+    pub fn set_error(&mut self, message: String) {
+        self.message = Some((message, Instant::now(), true));
+    }
+
+    pub fn get_message(&self) -> Option<&str> {
+        self.message.as_ref().map(|(m, _, _)| m.as_str())
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.message.as_ref().is_some_and(|(_, _, error)| *error)
+    }
+
+    /// Show or hide the summary of the profile
+    fn toggle_stats(&mut self) {
+        self.stats = match self.stats {
+            Some(_) => None,
+            None => Some(Stats::new(&self.source)),
+        };
+        self.stats_offset = 0;
+    }
+
+    /// Keys of the summary view. The panels are hidden, so only the arrows, to scroll
+    /// the summary, and the commands that don't act on the panels are run.
+    fn handle_stats_key(&mut self, key: Key) {
+        if key == Key::Esc {
+            self.stats = None;
+            return;
+        }
+
+        let command = match self.keymap.get(key) {
+            Some(command) => command.to_string(),
+            None => return,
+        };
+        let page = self.stats_height;
+
+        match command.as_str() {
+            "up" => self.stats_offset = self.stats_offset.saturating_sub(1),
+            "down" => self.stats_offset += 1,
+            "page-up" => self.stats_offset = self.stats_offset.saturating_sub(page),
+            "page-down" => self.stats_offset += page,
+            "stats" | "quit" | "suspend" => self.execute(&command),
+            _ => {}
+        }
+    }
+
+    /// Parse the profile again
+    fn reload(&mut self) {
+        match Profile::try_parse(&self.profile_path) {
+            Ok(profile) => {
+                self.source = profile;
+                let changed = self.resync();
+                self.set_message(format!("Profile reloaded, changed lines: {}", changed));
+            }
+            Err(e) => {
+                let e = e.to_string();
+                let e: Vec<&str> = e.split_whitespace().collect();
+                self.set_error(format!("Cannot reload profile: {}", e.join(" ")));
+            }
+        }
+    }
+
+    /// Sync the profile with the source files again, keeping the cursor on the same file
+    /// and line. Lines whose checkpoints changed are flashed in the checkpoint panel.
+    /// Returns the number of changed lines.
+    fn resync(&mut self) -> usize {
+        let profile = self.source.synced_in(&self.search_paths);
+
+        let current = self.get_current_item();
+        let current_key = current.and_then(ProfileItem::line_key);
+        let current_file = current.map(|item| item.get_file_info().borrow().path.clone());
+
+        let previous: BTreeMap<LineKey, BTreeSet<u32>> = self
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ProfileItem::Line(_, l) => item.line_key().map(|k| (k, l.checkpoints.clone())),
+                _ => None,
+            })
+            .collect();
+
+        let items: Vec<ProfileItem> = profile.items.iter().cloned().collect();
+
+        self.flashed = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| match item {
+                ProfileItem::Line(_, l) => match item.line_key().and_then(|k| previous.get(&k)) {
+                    Some(checkpoints) => *checkpoints != l.checkpoints,
+                    None => !l.checkpoints.is_empty(),
+                },
+                _ => false,
+            })
+            .map(|(i, _)| i)
+            .collect();
+        self.flash_start = Some(Instant::now());
+
+        let position = items
+            .iter()
+            .position(|item| current_key.is_some() && item.line_key() == current_key)
+            .or_else(|| {
+                items.iter().position(|item| {
+                    item.is_file()
+                        && Some(&item.get_file_info().borrow().path) == current_file.as_ref()
+                })
+            })
+            .unwrap_or(0);
+
+        self.profile = profile;
+        self.items = items;
+        self.expanded_folds.clear();
+
+        if self.stats.is_some() {
+            self.stats = Some(Stats::new(&self.source));
+        }
+        self.refresh_rows_at(position);
+
+        self.flashed.len()
+    }
+
+    /// True if the item has to be highlighted because it changed during the last update
+    pub fn is_flashed(&self, row: &Row) -> bool {
+        match row {
+            Row::Item(i) => self.flashed.contains(i),
+            Row::Fold { .. } => false,
+        }
+    }
+
+    /// Reload the profile each time it is written
+    pub fn set_watch(&mut self, watch: bool) {
+        self.watch = watch
+    }
+
+    /// Receive profile updates on the profile path, see `listen::listen`
+    pub fn set_listen(&mut self, listen: bool) {
+        self.listen = listen
+    }
+
+    /// Profiles to switch between, the first one being open
+    pub fn set_profiles(&mut self, profiles: Vec<PathBuf>) {
+        self.profiles = profiles
+    }
+
+    fn quit(&mut self) {
+        self.should_quit = true
+    }
+
+    /// Returns true if the display has to be refreshed
+    fn on_tick(&mut self) -> bool {
+        let mut redraw = false;
+
+        if let Some((_, since, _)) = &self.message {
+            if since.elapsed() >= MESSAGE_DURATION {
+                self.message = None;
+                redraw = true;
+            }
+        }
+
+        if let Some(since) = self.flash_start {
+            if since.elapsed() >= FLASH_DURATION {
+                self.flash_start = None;
+                self.flashed.clear();
+                redraw = true;
+            }
+        }
+
+        if let Some(since) = self.reload_pending {
+            if since.elapsed() >= RELOAD_DELAY {
+                self.reload_pending = None;
+                self.reload();
+                redraw = true;
+            }
+        }
+
+        if self.resync_pending {
+            self.resync_pending = false;
+            self.resync();
+            redraw = true;
+        }
+
+        redraw
+    }
+
+    fn on_message(&mut self, message: Message) {
+        match message {
+            Message::Status(s) => self.set_message(s),
+            Message::FileChanged(_) => self.reload_pending = Some(Instant::now()),
+            // Syncing is expensive, updates are batched until the next tick
+            Message::Update(update) => {
+                self.source.apply(update);
+                self.resync_pending = true;
+            }
+        }
+    }
+
+    /// Returns true if the display has to be refreshed
+    fn handle_event(&mut self, event: Event) -> bool {
+        match event {
+            Event::Key(key) => self.handle_key(key),
+            Event::Mouse(mouse) => self.handle_mouse(mouse),
+            Event::Resize => {}
+            Event::Tick => return self.on_tick(),
+            Event::Terminate => self.quit(),
+            Event::Suspend => self.should_suspend = true,
+            Event::Message(message) => self.on_message(message),
+        }
+
+        true
+    }
+
+    fn handle_key(&mut self, key: Key) {
+        if self.command_line.is_some() {
+            return self.handle_command_key(key);
+        }
+
+        if self.stats.is_some() {
+            return self.handle_stats_key(key);
+        }
+
+        if let Some(command) = self.keymap.get(key) {
+            let command = command.to_string();
+            self.execute(&command);
+        }
+    }
+
+    pub fn get_y_pos(&self) -> usize {
+        if self.get_item_count() <= self.height as usize {
+            return 0;
+        }
+
+        if self.y_pos >= (self.get_item_count() - (self.height as usize)) {
+            return self.get_item_count() - (self.height as usize);
+        }
+        self.y_pos
+    }
+
+    /// Position of the cursor relatively to the first displayed row
+    pub fn get_cursor_offset(&self) -> usize {
+        self.cursor.saturating_sub(self.get_y_pos())
+    }
+
+    /// Item under the cursor. For a folded row, this is the first folded line.
+    pub fn get_current_item(&self) -> Option<&ProfileItem> {
+        self.rows
+            .get(self.cursor)
+            .map(|row| &self.items[row.first_item()])
+    }
+
+    /// Function of the item under the cursor.
+    /// Lines without debug information inherit the function of the closest line above
+    /// them in the same file.
+    pub fn get_current_function(&self) -> Option<&str> {
+        let index = self.rows.get(self.cursor)?.first_item();
+        let current = &self.items[index];
+
+        self.items[..=index]
+            .iter()
+            .rev()
+            .take_while(|item| !item.is_file() && item.is_in_same_file(current))
+            .find_map(|item| match item {
+                ProfileItem::Line(_, l) => l.function.as_ref().map(|f| f.as_str()),
+                _ => None,
+            })
+    }
+
+    /// Number of the last line of the file under the cursor
+    pub fn get_current_file_length(&self) -> Option<usize> {
+        let index = self.rows.get(self.cursor)?.first_item();
+        let current = &self.items[index];
+
+        self.items[index..]
+            .iter()
+            .take_while(|item| item.is_in_same_file(current))
+            .filter_map(|item| match item {
+                ProfileItem::Line(_, l) => Some(l.nb),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Short description of the active view modes
+    pub fn get_modes(&self) -> Vec<String> {
+        let mut modes = vec![];
+
+        if self.watch {
+            modes.push(String::from("watch"));
+        }
+
+        if self.listen {
+            modes.push(String::from("listen"));
+        }
+
+        if let Some(i) = self.profiles.iter().position(|p| *p == self.profile_path) {
+            if self.profiles.len() > 1 {
+                modes.push(format!("profile {}/{}", i + 1, self.profiles.len()));
+            }
+        }
+
+        if self.folding {
+            modes.push(format!("fold ±{}", self.fold_context));
+        }
+
+        if self.h_offset > 0 {
+            modes.push(format!("col +{}", self.h_offset));
+        }
+
+        if let Some(id) = self.focus {
+            modes.push(format!("focus {}", id));
+        }
+
+        if let Some(filter) = &self.filter {
+            modes.push(format!("filter {}", filter.expression));
+        }
+
+        modes
+    }
+
+    pub fn get_item_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn set_height(&mut self, height: u16) {
+        self.height = height
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height as usize
+    }
+
+    pub fn new<P: AsRef<Path>>(path: P, source: Profile, config: &Config) -> App {
+        let profile = source.synced_in(&config.search_paths);
+        let items: Vec<ProfileItem> = profile.items.iter().cloned().collect();
+
+        let mut app = App {
+            profile_path: path.as_ref().to_path_buf(),
+            profiles: vec![path.as_ref().to_path_buf()],
+            profile,
+            source,
+            should_quit: false,
+            rows: fold::unfolded(&items),
+            tab_width: config.view.tab_width,
+            h_offset: 0,
+            layout: config.layout.clone(),
+            areas: vec![],
+            focus: None,
+            drag: None,
+            filter: None,
+            stats: None,
+            stats_offset: 0,
+            stats_height: 0,
+            keymap: config.keymap.clone(),
+            theme: config.theme,
+            search_paths: config.search_paths.clone(),
+            command_line: None,
+            history: vec![],
+            items,
+            folding: config.view.fold,
+            fold_context: config.view.fold_context,
+            expanded_folds: BTreeSet::new(),
+            cursor: 0,
+            y_pos: 0,
+            height: 0,
+            message: None,
+            watch: false,
+            listen: false,
+            reload_pending: None,
+            resync_pending: false,
+            should_suspend: false,
+            flashed: BTreeSet::new(),
+            flash_start: None,
+        };
+
+        app.refresh_rows();
+        app
+    }
+
+    pub fn run(&mut self) {
+        // Declared first so that it restores the terminal last
+        let guard = TerminalGuard::new().expect("Cannot set up the terminal");
+        let backend = TermionBackend::new(stdout());
+
+        self.should_quit = false;
+
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.clear().unwrap();
+
+        let events = Events::new(TICK_RATE);
+        let mut redraw = true;
+
+        // Dropping the watcher stops the watch
+        let mut watcher = None;
+        let mut watched = None;
+
+        // Dropping the listener removes its socket
+        let _listener = if self.listen {
+            match listen::listen(&self.profile_path, events.sender()) {
+                Ok(listener) => Some(listener),
+                Err(e) => {
+                    self.set_error(format!("Cannot listen: {}", e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        while !self.should_quit {
+            // Another profile may have been opened
+            if self.watch && watched.as_ref() != Some(&self.profile_path) {
+                watched = Some(self.profile_path.clone());
+                watcher = match watch::watch_file(&self.profile_path, events.sender()) {
+                    Ok(watcher) => Some(watcher),
+                    Err(e) => {
+                        self.set_error(format!("Cannot watch profile: {}", e));
+                        None
+                    }
+                };
+            }
+
+            if redraw {
+                let _ = terminal.draw(|f| crate::ui::draw(f, self));
+            }
+
+            let event = match events.next() {
+                Ok(event) => event,
+                Err(_) => break,
+            };
+
+            redraw = self.handle_event(event);
+
+            // Handle pending events before drawing again
+            while let Some(event) = events.try_next() {
+                redraw |= self.handle_event(event);
+            }
+
+            if self.should_suspend {
+                self.should_suspend = false;
+
+                if let Err(e) = guard.suspend() {
+                    self.set_error(format!("Cannot suspend: {}", e));
+                }
+
+                // The screen has to be drawn again from scratch
+                if let Ok(size) = terminal.size() {
+                    let _ = terminal.resize(size);
+                }
+                redraw = true;
+            }
+        }
+
+        drop(watcher);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::App;
+    use crate::config::Config;
+    use crate::model::profile::{Profile, ProfileItem};
+    use crate::ui::layout::{PanelArea, PanelKind, PanelSize};
+    use termion::event::{Key, MouseButton, MouseEvent};
+    use tui::layout::Rect;
+
+    const PROFILE: &str = "[checkpoint id]
+0 -> memviz_begin
+
+[met checkpoint]
+fl=assets/test/hello/hello.c
+fn=main
+9 [0x1089ac;0x1089c4] -> 0
+13 [0x1089d1;0x108a29] -> 0 1
+";
+
+    fn line_index(app: &App, nb: usize) -> usize {
+        app.items
+            .iter()
+            .position(|item| match item {
+                ProfileItem::Line(_, l) => l.nb == nb,
+                _ => false,
+            })
+            .unwrap()
+    }
+
+    fn area(panel: PanelKind, x: u16, width: u16) -> PanelArea {
+        PanelArea {
+            panel,
+            rect: Rect::new(x, 0, width, 20),
+            header: Rect::new(x + 1, 1, width - 2, 2),
+            body: Rect::new(x + 1, 3, width - 2, 10),
+            header_cells: vec![
+                (0, Rect::new(x + 1, 1, 4, 1)),
+                (1, Rect::new(x + 5, 1, 4, 1)),
+            ],
+        }
+    }
+
+    #[test]
+    fn mouse() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let mut app = App::new("profile", profile, &Config::default());
+        app.set_height(10);
+        app.areas = vec![
+            area(PanelKind::Checkpoints, 0, 40),
+            area(PanelKind::Addresses, 40, 30),
+        ];
+
+        // Coordinates start at 1
+        app.handle_mouse(MouseEvent::Press(MouseButton::Left, 7, 2));
+        assert_eq!(app.focus, Some(1));
+        app.handle_mouse(MouseEvent::Press(MouseButton::Left, 7, 2));
+        assert_eq!(app.focus, None);
+
+        app.handle_mouse(MouseEvent::Press(MouseButton::Left, 45, 7));
+        assert_eq!(app.cursor, 3);
+        assert_eq!(app.layout.selected(), Some(PanelKind::Addresses));
+
+        app.handle_mouse(MouseEvent::Press(MouseButton::WheelDown, 45, 7));
+        assert_eq!((app.get_y_pos(), app.cursor), (3, 3));
+        app.handle_mouse(MouseEvent::Press(MouseButton::WheelDown, 45, 7));
+        assert_eq!((app.get_y_pos(), app.cursor), (6, 6));
+
+        // The checkpoint panel fills the space, the address panel is resized
+        app.handle_mouse(MouseEvent::Press(MouseButton::Left, 40, 10));
+        app.handle_mouse(MouseEvent::Hold(30, 10));
+        app.handle_mouse(MouseEvent::Release(30, 10));
+        assert_eq!(
+            app.layout.size(PanelKind::Addresses),
+            Some(PanelSize::Length(41))
+        );
+    }
+
+    #[test]
+    fn toggle_stats() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let mut app = App::new("profile", profile, &Config::default());
+
+        app.handle_key(Key::Char('s'));
+        assert_eq!(app.stats.as_ref().map(|s| s.lines), Some(5));
+        app.handle_key(Key::Esc);
+        assert!(app.stats.is_none());
+        app.handle_key(Key::Char('s'));
+        app.handle_key(Key::Char('s'));
+        assert!(app.stats.is_none());
+
+        // The panels don't move while the summary covers them
+        app.set_height(10);
+        app.stats_height = 10;
+        let folding = app.folding;
+        app.handle_key(Key::Char('s'));
+        app.handle_key(Key::Down);
+        app.handle_key(Key::PageDown);
+        app.handle_key(Key::Char('z'));
+        app.handle_mouse(MouseEvent::Press(MouseButton::WheelDown, 1, 1));
+        assert_eq!(app.stats_offset, 14);
+        assert_eq!((app.cursor, app.get_y_pos(), app.folding), (0, 0, folding));
+        app.handle_key(Key::Up);
+        assert_eq!(app.stats_offset, 13);
+
+        app.handle_key(Key::Esc);
+        app.handle_key(Key::Char('s'));
+        assert_eq!(app.stats_offset, 0);
+    }
+
+    #[test]
+    fn reload_keeps_cursor_and_flashes_changes() {
+        let dir = std::env::temp_dir().join(format!("phaseviz-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("profile");
+        std::fs::write(&path, PROFILE).unwrap();
+
+        let mut app = App::new(&path, Profile::parse(&path), &Config::default());
+        app.cursor = line_index(&app, 13);
+
+        let updated = PROFILE.replace("-> 0 1", "-> 0") + "19 [0x108a4e;0x108a55] -> 0\n";
+        std::fs::write(&path, updated).unwrap();
+        app.reload();
+
+        match app.get_current_item() {
+            Some(ProfileItem::Line(_, l)) => assert_eq!(l.nb, 13),
+            item => panic!("unexpected item {:?}", item),
+        }
+        assert_eq!(
+            app.flashed,
+            bt_set!(line_index(&app, 13), line_index(&app, 19))
+        );
+
+        // A broken profile is reported and the current one is kept
+        std::fs::write(&path, "[met checkpoint]\n13 ->").unwrap();
+        app.reload();
+        assert!(app
+            .get_message()
+            .unwrap()
+            .starts_with("Cannot reload profile"));
+        assert_eq!(app.items.len(), app.profile.items.len());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /*
+    use crate::profile::{Profile, FileInfo, ProfileItem, LineInfo};
+    use crate::app::{ProfileItem};
+
+    #[test]
+    fn test_iter_items(){
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+
+        let items:Vec<ProfileItem> = profile.iter_items().collect();
+        let f = &profile.file_sections.first().unwrap();
+        let lines: Vec<&CodeLine> = f.lines.iter().collect();
+
+        let expected = vec![
+            ProfileItem::FileHeader(&profile.file_sections.first().unwrap()),
+            ProfileItem::FunctionLine(lines[0]),
+            ProfileItem::FunctionLine(lines[1]),
+            ProfileItem::FunctionLine(lines[2]),
+            ProfileItem::FunctionLine(lines[3]),
+            ProfileItem::FunctionLine(lines[4]),
+        ];
+
+        for (i, item) in items.iter().enumerate() {
+            assert_eq!(&expected[i], item)
+        }
+    }*/
+}
@@ -0,0 +1,133 @@
+use std::{
+    io::{self, stdout, Write},
+    mem::MaybeUninit,
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, Once,
+    },
+};
+
+use signal_hook::{consts::SIGSTOP, low_level::raise};
+use termion::{cursor, screen};
+
+/// Report clicks, drags and wheel, with coordinates past the 223rd column
+const ENTER_MOUSE: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h";
+const EXIT_MOUSE: &str = "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l";
+
+/// Terminal attributes before entering raw mode
+static ORIGINAL_ATTRIBUTES: Mutex<Option<libc::termios>> = Mutex::new(None);
+static PANIC_HOOK: Once = Once::new();
+/// Whether a guard owns the terminal, so that the panic hook restores it
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+fn get_attributes() -> io::Result<libc::termios> {
+    let mut attributes = MaybeUninit::uninit();
+
+    if unsafe { libc::tcgetattr(libc::STDOUT_FILENO, attributes.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(unsafe { attributes.assume_init() })
+}
+
+fn set_attributes(attributes: &libc::termios) -> io::Result<()> {
+    if unsafe { libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSANOW, attributes) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Switch to raw mode and to the alternate screen, hide the cursor and enable the mouse
+fn enter() -> io::Result<()> {
+    let attributes = get_attributes()?;
+    let mut raw = attributes;
+
+    ORIGINAL_ATTRIBUTES
+        .lock()
+        .unwrap()
+        .get_or_insert(attributes);
+
+    unsafe { libc::cfmakeraw(&mut raw) };
+    set_attributes(&raw)?;
+
+    let mut out = stdout();
+    write!(
+        out,
+        "{}{}{}",
+        screen::ToAlternateScreen,
+        cursor::Hide,
+        ENTER_MOUSE
+    )?;
+    out.flush()
+}
+
+/// Put the terminal back in the state it was before the guard was created.
+/// Restoring an already restored terminal does nothing harmful.
+pub fn restore() {
+    // The lock may be poisoned if a panic happened while holding it
+    let original = match ORIGINAL_ATTRIBUTES.lock() {
+        Ok(original) => *original,
+        Err(poisoned) => *poisoned.into_inner(),
+    };
+
+    if let Some(attributes) = original {
+        let _ = set_attributes(&attributes);
+    }
+
+    let mut out = stdout();
+    let _ = write!(
+        out,
+        "{}{}{}",
+        EXIT_MOUSE,
+        cursor::Show,
+        screen::ToMainScreen
+    );
+    let _ = out.flush();
+}
+
+/// Owns the terminal while the application runs.
+/// The terminal is restored when the guard is dropped, and before a panic message is
+/// printed.
+pub struct TerminalGuard {}
+
+impl TerminalGuard {
+    pub fn new() -> io::Result<TerminalGuard> {
+        PANIC_HOOK.call_once(|| {
+            let default_hook = panic::take_hook();
+
+            panic::set_hook(Box::new(move |info| {
+                if ACTIVE.swap(false, Ordering::SeqCst) {
+                    restore();
+                }
+                default_hook(info);
+            }));
+        });
+
+        ACTIVE.store(true, Ordering::SeqCst);
+
+        if let Err(e) = enter() {
+            ACTIVE.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+
+        Ok(TerminalGuard {})
+    }
+
+    /// Give the terminal back to the shell and stop the process until it is resumed
+    /// (with `fg` for instance)
+    pub fn suspend(&self) -> io::Result<()> {
+        restore();
+        raise(SIGSTOP)?;
+
+        enter()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        ACTIVE.store(false, Ordering::SeqCst);
+        restore();
+    }
+}
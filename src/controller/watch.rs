@@ -0,0 +1,69 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use super::event::{Event, Message};
+
+/// Watch a file and post `Message::FileChanged` each time it is written, created or
+/// replaced. The parent directory is watched rather than the file itself, so that the
+/// file can be deleted and recreated, or replaced by a rename.
+/// The watch stops when the returned watcher is dropped.
+pub fn watch_file<P: AsRef<Path>>(
+    path: P,
+    tx: Sender<Event>,
+) -> notify::Result<RecommendedWatcher> {
+    let path = path.as_ref().canonicalize()?;
+    let dir = path
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/"));
+
+    let watched = path.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(_) => return,
+        };
+
+        let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+
+        if relevant && event.paths.contains(&watched) {
+            let _ = tx.send(Event::Message(Message::FileChanged(watched.clone())));
+        }
+    })?;
+
+    watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::watch_file;
+    use crate::controller::event::{Event, Message};
+    use std::{sync::mpsc::channel, time::Duration};
+
+    #[test]
+    fn notify_on_write() {
+        let dir = std::env::temp_dir().join(format!("phaseviz-watch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("profile");
+        std::fs::write(&path, "a").unwrap();
+
+        let (tx, rx) = channel();
+        let _watcher = watch_file(&path, tx).unwrap();
+
+        std::fs::write(dir.join("other"), "b").unwrap();
+        std::fs::write(&path, "c").unwrap();
+
+        let event = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let expected = path.canonicalize().unwrap();
+
+        assert_eq!(event, Event::Message(Message::FileChanged(expected)));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,35 @@
+//! Reading memviz checkpoint profiles.
+//!
+//! A profile records, for each line of code instrumented by memviz, the address range
+//! of its instructions and the checkpoints it met. [`Profile::try_parse`] reads a
+//! profile file, whose items are sorted by file then by line:
+//!
+//! ```
+//! use phaseviz::{Profile, ProfileItem};
+//!
+//! let profile = Profile::try_parse("assets/test/memviz.chekpoint.28516").unwrap();
+//! assert_eq!(profile.checkpoint_name(1), Some("Before_hello"));
+//!
+//! for item in profile.items.iter() {
+//!     match item {
+//!         ProfileItem::File(f) => println!("{}", f.borrow().path.expand().display()),
+//!         ProfileItem::Line(_, l) => println!("{} {:?}", l.nb, l.checkpoints),
+//!     }
+//! }
+//! ```
+//!
+//! The source lines are not read by the parser, [`Profile::synced`] returns a copy of
+//! the profile with every line of the source files.
+
+#![warn(missing_docs)]
+
+#[macro_use]
+extern crate pest_derive;
+extern crate pest;
+
+#[doc(hidden)]
+#[macro_use]
+pub mod utils;
+pub mod model;
+
+pub use model::profile::{FileInfo, LineInfo, ParseError, PathInfo, Profile, ProfileItem};
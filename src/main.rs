@@ -1,33 +1,25 @@
-#![feature(map_first_last, clamp, let_chains)]
-
+#[cfg(test)]
 #[macro_use]
-extern crate pest_derive;
-extern crate pest;
+extern crate phaseviz;
 
-#[macro_use]
-mod utils;
+mod cli;
+mod config;
 mod controller;
-mod model;
 mod ui;
 
-fn print_usage(args: Vec<String>) {
-    eprintln!("Usage: {} path_to_profile", args[0]);
-}
+use clap::Parser;
+use phaseviz::model;
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-
-    if args.len() < 2 {
-        eprintln!("PhaseViz error: No path to profile specified");
-        print_usage(args);
-        std::process::exit(1);
-    }
-
-    let profile_path = &args[1];
+    let cli = cli::Cli::parse();
 
-    let profile = model::profile::Profile::parse(profile_path);
-    let synced_profile = profile.synced();
-    let mut app = controller::App::new(&synced_profile);
+    let code = match cli::run(cli) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("PhaseViz error: {}", e);
+            cli::EXIT_FAILURE
+        }
+    };
 
-    app.run();
+    std::process::exit(code);
 }
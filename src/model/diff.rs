@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::profile::{LineKey, Profile, ProfileItem};
+
+/// A line whose checkpoints differ between two profiles.
+/// Checkpoints are compared by name, ids being only meaningful within a profile.
+/// `None` means that the line is not in the profile.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LineDiff {
+    /// The line, see `ProfileItem::line_key`
+    pub key: LineKey,
+    /// Checkpoints met by the line in the old profile
+    pub old: Option<BTreeSet<String>>,
+    /// Checkpoints met by the line in the new profile
+    pub new: Option<BTreeSet<String>>,
+}
+
+fn checkpoints_by_line(profile: &Profile) -> BTreeMap<LineKey, BTreeSet<String>> {
+    profile
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ProfileItem::File(_) => None,
+            ProfileItem::Line(_, l) => {
+                let names = l
+                    .checkpoints
+                    .iter()
+                    .map(|id| match profile.checkpoint_name(*id) {
+                        Some(name) => String::from(name),
+                        None => id.to_string(),
+                    })
+                    .collect();
+
+                item.line_key().map(|key| (key, names))
+            }
+        })
+        .collect()
+}
+
+/// Lines added, removed, or met by other checkpoints, in the order of the lines
+pub fn diff(old: &Profile, new: &Profile) -> Vec<LineDiff> {
+    let mut old = checkpoints_by_line(old);
+    let new = checkpoints_by_line(new);
+    let mut diffs = vec![];
+
+    for (key, new_names) in new {
+        match old.remove(&key) {
+            Some(old_names) if old_names == new_names => {}
+            old_names => diffs.push(LineDiff {
+                key,
+                old: old_names,
+                new: Some(new_names),
+            }),
+        }
+    }
+
+    diffs.extend(old.into_iter().map(|(key, old_names)| LineDiff {
+        key,
+        old: Some(old_names),
+        new: None,
+    }));
+
+    diffs.sort_by(|a, b| a.key.cmp(&b.key));
+    diffs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff;
+    use crate::model::profile::{CodeLoc, Profile, Update};
+    use std::collections::BTreeSet;
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn diff_profiles() {
+        let old = Profile::parse("assets/test/memviz.chekpoint.28516");
+        assert_eq!(diff(&old, &old), vec![]);
+
+        let mut new = Profile::parse("assets/test/memviz.chekpoint.28516");
+        new.apply(Update::CodeLoc(CodeLoc {
+            file: String::from("assets/test/hello/hello.c"),
+            function: String::from("main"),
+            nb: 15,
+            addr_range: (0x108a2d, 0x108a34),
+            checkpoints: bt_set!(0),
+        }));
+        new.apply(Update::CodeLoc(CodeLoc {
+            file: String::from("assets/test/hello/hello.c"),
+            function: String::from("main"),
+            nb: 21,
+            addr_range: (0x108a56, 0x108a60),
+            checkpoints: bt_set!(1),
+        }));
+
+        let diffs = diff(&old, &new);
+        assert_eq!(diffs.len(), 2);
+
+        assert_eq!(diffs[0].key.1, 15);
+        assert_eq!(diffs[0].old, Some(names(&["Before_hello"])));
+        assert_eq!(diffs[0].new, Some(names(&["Before_hello", "memviz_begin"])));
+
+        assert_eq!(diffs[1].key.1, 21);
+        assert_eq!(diffs[1].old, None);
+
+        let reversed = diff(&new, &old);
+        assert_eq!(reversed[1].new, None);
+    }
+}
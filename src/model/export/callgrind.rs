@@ -0,0 +1,203 @@
+//! Profiles for callgrind viewers such as KCachegrind.
+//!
+//! Each checkpoint is an event, and each line costs 1 of the events of the checkpoints
+//! it met. Viewers then show, per checkpoint, the lines and functions that met it.
+//! Lines are positioned by their first instruction address too, when the profile has
+//! addresses.
+//!
+//! ```text
+//! # callgrind format
+//! version: 1
+//! creator: phaseviz 0.1.0
+//! positions: instr line
+//! event: Before_hello : Before hello
+//! events: memviz_begin Before_hello
+//! summary: 3 3
+//!
+//! fl=(1) assets/test/hello/hello.c
+//! fn=(1) main
+//! 0x1089ac 9 1 0
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::identifier;
+use crate::model::profile::{Profile, ProfileItem};
+
+/// Names written with callgrind name compression: `(ID) NAME` the first time, then
+/// `(ID)`
+#[derive(Default)]
+struct Names(BTreeMap<String, usize>);
+
+impl Names {
+    fn compressed(&mut self, name: &str) -> String {
+        if let Some(id) = self.0.get(name) {
+            return format!("({})", id);
+        }
+
+        let id = self.0.len() + 1;
+        self.0.insert(String::from(name), id);
+        format!("({}) {}", id, name)
+    }
+}
+
+/// Event names of the checkpoints, by id. Names are made unique by appending the id
+/// of the checkpoint, then a counter if this name is taken too.
+fn event_names(profile: &Profile) -> BTreeMap<u32, String> {
+    let mut names = BTreeMap::new();
+    let mut used = BTreeSet::new();
+
+    for (id, name) in profile.checkpoints.iter() {
+        let mut event = identifier(name);
+
+        if event.is_empty() || event.starts_with(|c: char| c.is_ascii_digit()) {
+            event = format!("cp{}", event);
+        }
+        if !used.insert(event.clone()) {
+            let base = format!("{}_{}", event, id);
+            event = base.clone();
+
+            let mut n = 1;
+            while !used.insert(event.clone()) {
+                n += 1;
+                event = format!("{}_{}", base, n);
+            }
+        }
+
+        names.insert(*id, event);
+    }
+
+    names
+}
+
+/// The profile as a callgrind profile data file
+pub fn dump(profile: &Profile) -> String {
+    let events = event_names(profile);
+    let has_addresses = profile.items.iter().any(|item| match item {
+        ProfileItem::Line(_, l) => l.addr_range != (0, 0),
+        ProfileItem::File(_) => false,
+    });
+
+    let mut text = String::from("# callgrind format\nversion: 1\n");
+    text.push_str(&format!(
+        "creator: phaseviz {}\n",
+        env!("CARGO_PKG_VERSION")
+    ));
+    text.push_str(match has_addresses {
+        true => "positions: instr line\n",
+        false => "positions: line\n",
+    });
+
+    for (id, event) in events.iter() {
+        let name = &profile.checkpoints[id];
+
+        if name != event {
+            text.push_str(&format!("event: {} : {}\n", event, name));
+        }
+    }
+    let names: Vec<&str> = events.values().map(|e| e.as_str()).collect();
+    text.push_str(&format!("events: {}\n", names.join(" ")));
+
+    let mut summary: BTreeMap<u32, usize> = BTreeMap::new();
+    let mut body = String::new();
+    let mut files = Names::default();
+    let mut functions = Names::default();
+    let mut function = None;
+
+    for item in profile.items.iter() {
+        let l = match item {
+            ProfileItem::File(f) => {
+                let path = f.borrow().path.expand().display().to_string();
+                body.push_str(&format!("\nfl={}\n", files.compressed(&path)));
+                function = None;
+                continue;
+            }
+            ProfileItem::Line(_, l) => l,
+        };
+
+        let name = l.function.as_ref().map_or("???", |f| f.as_str());
+        if function != Some(name) {
+            body.push_str(&format!("fn={}\n", functions.compressed(name)));
+            function = Some(name);
+        }
+
+        if has_addresses {
+            body.push_str(&format!("{:#x} ", l.addr_range.0));
+        }
+        body.push_str(&l.nb.to_string());
+
+        for id in events.keys() {
+            let met = l.checkpoints.contains(id);
+
+            body.push_str(if met { " 1" } else { " 0" });
+            *summary.entry(*id).or_default() += met as usize;
+        }
+        body.push('\n');
+    }
+
+    let summary: Vec<String> = events
+        .keys()
+        .map(|id| summary.get(id).cloned().unwrap_or(0).to_string())
+        .collect();
+    text.push_str(&format!("summary: {}\n", summary.join(" ")));
+    text.push_str(&body);
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dump, event_names};
+    use crate::model::profile::Profile;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn dump_profile() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let text = dump(&profile);
+
+        assert!(text.starts_with("# callgrind format\nversion: 1\ncreator: phaseviz "));
+        assert!(text.ends_with(
+            "positions: instr line\n\
+             events: memviz_begin Before_hello\n\
+             summary: 3 3\n\
+             \n\
+             fl=(1) assets/test/hello/hello.c\n\
+             fn=(1) main\n\
+             0x1089ac 9 1 0\n\
+             0x1089c6 11 1 0\n\
+             0x1089d1 13 1 1\n\
+             0x108a2d 15 0 1\n\
+             0x108a4e 19 0 1\n"
+        ));
+
+        let mut profile = profile;
+        profile.checkpoints.insert(0, String::from("Before hello"));
+        profile.checkpoints.insert(2, String::from("Before_hello"));
+        profile.checkpoints.insert(3, String::from("3"));
+
+        let events: Vec<String> = event_names(&profile).into_values().collect();
+        assert_eq!(
+            events,
+            vec!["Before_hello", "Before_hello_1", "Before_hello_2", "cp3"]
+        );
+        assert!(dump(&profile).contains("event: Before_hello : Before hello\n"));
+
+        // Names with a suffix already in use
+        for names in [["a_1", "a", "a"], ["a_2", "a", "a"], ["a", "a", "a_1"]].iter() {
+            profile.checkpoints = names
+                .iter()
+                .enumerate()
+                .map(|(id, name)| (id as u32, String::from(*name)))
+                .collect();
+
+            let events: BTreeSet<String> = event_names(&profile).into_values().collect();
+            assert_eq!(events.len(), 3);
+        }
+
+        profile.checkpoints.insert(0, String::from("a_2"));
+        profile.checkpoints.insert(2, String::from("a"));
+        let events: Vec<String> = event_names(&profile).into_values().collect();
+        assert_eq!(events, vec!["a_2", "a", "a_2_2"]);
+    }
+}
@@ -0,0 +1,109 @@
+//! Tables of lines for spreadsheets, as CSV or TSV.
+//!
+//! The header is `file,function,line,addr_start,addr_end,size` followed, when lines are
+//! aggregated, by `lines`, then by the names of the checkpoints. Each row is a line of
+//! the profile, with `1` in the columns of the checkpoints it met and `0` in the others.
+//! Aggregated rows count the lines that met each checkpoint instead.
+//!
+//! ```text
+//! file,function,line,addr_start,addr_end,size,memviz_begin,Before_hello
+//! assets/test/hello/hello.c,main,13,0x1089d1,0x108a29,88,1,1
+//! ```
+
+use super::{rows, Aggregation};
+use crate::model::profile::Profile;
+
+/// Quote a field if it contains the separator, a quote or a line break
+fn field(value: &str, separator: char) -> String {
+    if value.contains([separator, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+fn record(fields: &[String], separator: char) -> String {
+    let fields: Vec<String> = fields.iter().map(|f| field(f, separator)).collect();
+    let mut record = fields.join(&separator.to_string());
+
+    record.push('\n');
+    record
+}
+
+/// The lines of the profile, separated by `separator`: `,` for CSV, `\t` for TSV
+pub fn table(profile: &Profile, aggregation: Aggregation, separator: char) -> String {
+    let mut header: Vec<String> = ["file", "function", "line", "addr_start", "addr_end", "size"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+    if aggregation != Aggregation::Line {
+        header.push(String::from("lines"));
+    }
+    header.extend(profile.checkpoints.values().cloned());
+
+    let mut text = record(&header, separator);
+
+    for row in rows(profile, aggregation) {
+        let mut fields = vec![
+            row.path.expand().display().to_string(),
+            row.function.clone().unwrap_or_default(),
+            row.nb.to_string(),
+            format!("{:#x}", row.addr_range.0),
+            format!("{:#x}", row.addr_range.1),
+            row.size.to_string(),
+        ];
+
+        if aggregation != Aggregation::Line {
+            fields.push(row.lines.to_string());
+        }
+        fields.extend(
+            profile
+                .checkpoints
+                .keys()
+                .map(|id| row.counts.get(id).cloned().unwrap_or(0).to_string()),
+        );
+
+        text.push_str(&record(&fields, separator));
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{field, table};
+    use crate::model::{export::Aggregation, profile::Profile};
+
+    #[test]
+    fn write_tables() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+
+        let csv = table(&profile, Aggregation::Line, ',');
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[0],
+            "file,function,line,addr_start,addr_end,size,memviz_begin,Before_hello"
+        );
+        assert_eq!(
+            lines[1],
+            "assets/test/hello/hello.c,main,9,0x1089ac,0x1089c4,24,1,0"
+        );
+        assert_eq!(
+            lines[3],
+            "assets/test/hello/hello.c,main,13,0x1089d1,0x108a29,88,1,1"
+        );
+
+        let tsv = table(&profile, Aggregation::Function, '\t');
+        assert_eq!(
+            tsv,
+            "file\tfunction\tline\taddr_start\taddr_end\tsize\tlines\tmemviz_begin\tBefore_hello\n\
+             assets/test/hello/hello.c\tmain\t9\t0x1089ac\t0x108a55\t131\t5\t3\t3\n"
+        );
+
+        assert_eq!(field("a,b", ','), "\"a,b\"");
+        assert_eq!(field("a\"b", '\t'), "\"a\"\"b\"");
+        assert_eq!(field("a,b", '\t'), "a,b");
+    }
+}
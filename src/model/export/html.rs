@@ -0,0 +1,256 @@
+//! A report of a profile as a static HTML site, readable offline.
+//!
+//! `index.html` lists the files with the number of lines that met each checkpoint, and
+//! links to a page per file (`file-1.html`...) showing its source with the checkpoint
+//! matrix next to each line. Pages embed their style and script, and filter their rows
+//! by checkpoint in the browser.
+
+use std::collections::BTreeMap;
+
+use crate::model::profile::{LineInfo, Profile, ProfileItem};
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 1em 2em; }
+table { border-collapse: collapse; }
+th, td { padding: 0 0.4em; }
+th.cp { vertical-align: bottom; }
+th.cp span { writing-mode: vertical-rl; transform: rotate(180deg); white-space: nowrap; }
+tr:hover { background: #eef; }
+td.num { text-align: right; }
+td.m { color: #1a7f37; text-align: center; }
+td.n { color: #bbb; text-align: center; }
+td.addr, td.nb { color: #666; font-family: monospace; white-space: nowrap; }
+td.nb { text-align: right; border-right: 1px solid #ccc; }
+td.src { font-family: monospace; white-space: pre; }
+tr.nocode td.src, td.fn { color: #888; font-style: italic; }
+#filter { margin: 1em 0; }
+#filter label { margin-right: 1em; white-space: nowrap; }
+";
+
+const SCRIPT: &str = "\
+const boxes = Array.from(document.querySelectorAll('#filter input'));
+function filter() {
+  const selected = boxes.filter(b => b.checked).map(b => b.value);
+  for (const row of document.querySelectorAll('tr[data-cp]')) {
+    const met = row.dataset.cp.split(' ');
+    row.hidden = selected.length > 0 && !selected.some(id => met.includes(id));
+  }
+}
+boxes.forEach(b => b.addEventListener('change', filter));
+";
+
+/// Escape the HTML (and XML) special characters
+pub(super) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn page(title: &str, tab_width: usize, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>\n{}td.src {{ tab-size: {}; }}\n</style>\n</head>\n\
+         <body>\n{}<script>\n{}</script>\n</body>\n</html>\n",
+        escape(title),
+        STYLE,
+        tab_width,
+        body,
+        SCRIPT
+    )
+}
+
+/// Check boxes showing only the rows that met one of the checked checkpoints
+fn filter_form(profile: &Profile) -> String {
+    let mut form = String::from("<form id=\"filter\">Show the rows that met: ");
+
+    for (id, name) in profile.checkpoints.iter() {
+        form.push_str(&format!(
+            "<label><input type=\"checkbox\" value=\"{}\"> {}</label>",
+            id,
+            escape(name)
+        ));
+    }
+
+    form.push_str("</form>\n");
+    form
+}
+
+fn checkpoint_headers(profile: &Profile) -> String {
+    profile
+        .checkpoints
+        .iter()
+        .map(|(id, name)| {
+            format!(
+                "<th class=\"cp\" title=\"{}: {}\"><span>{}</span></th>",
+                id,
+                escape(name),
+                escape(name)
+            )
+        })
+        .collect()
+}
+
+fn data_checkpoints<'a, I: Iterator<Item = &'a u32>>(checkpoints: I) -> String {
+    let ids: Vec<String> = checkpoints.map(|id| id.to_string()).collect();
+    ids.join(" ")
+}
+
+/// A row of a file page
+fn line_row(profile: &Profile, l: &LineInfo) -> String {
+    let has_code = l.addr_range != (0, 0) || !l.checkpoints.is_empty();
+    let mut row = format!(
+        "<tr data-cp=\"{}\"{}>",
+        data_checkpoints(l.checkpoints.iter()),
+        if has_code { "" } else { " class=\"nocode\"" }
+    );
+
+    for id in profile.checkpoints.keys() {
+        row.push_str(match l.checkpoints.contains(id) {
+            true => "<td class=\"m\">◼</td>",
+            false if has_code => "<td class=\"n\">·</td>",
+            false => "<td></td>",
+        });
+    }
+
+    let addr = match l.addr_range {
+        (0, 0) => String::new(),
+        (start, end) => format!("{:#x}-{:#x}", start, end),
+    };
+    let content = match (&l.line_content, &l.function) {
+        (Some(content), _) => format!("<td class=\"src\">{}</td>", escape(content)),
+        (None, Some(function)) => {
+            format!("<td class=\"fn\">in function: {}</td>", escape(function))
+        }
+        (None, None) => String::from("<td></td>"),
+    };
+
+    row.push_str(&format!(
+        "<td class=\"addr\">{}</td><td class=\"nb\">{}</td>{}</tr>\n",
+        addr, l.nb, content
+    ));
+    row
+}
+
+/// The pages of the report, by file name. The profile should be synced with its source
+/// files, for the pages to show them.
+pub fn report(profile: &Profile, title: &str, tab_width: usize) -> BTreeMap<String, String> {
+    let mut pages = BTreeMap::new();
+    let mut index = String::new();
+    let headers = checkpoint_headers(profile);
+    let mut file_pages = 0;
+
+    for section in profile.file_sections() {
+        let mut section = section.peekable();
+        let file = match section.peek() {
+            Some(item) => item.get_file_info(),
+            None => continue,
+        };
+        let path = file.borrow().path.expand().display().to_string();
+
+        let mut rows = String::new();
+        let mut lines = 0;
+        let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+
+        for item in section {
+            if let ProfileItem::Line(_, l) = item {
+                if l.addr_range != (0, 0) || !l.checkpoints.is_empty() {
+                    lines += 1;
+                }
+                for id in l.checkpoints.iter() {
+                    *counts.entry(*id).or_default() += 1;
+                }
+
+                rows.push_str(&line_row(profile, l));
+            }
+        }
+
+        file_pages += 1;
+        let name = format!("file-{}.html", file_pages);
+        let body = format!(
+            "<h1>{}</h1>\n<p><a href=\"index.html\">{}</a></p>\n{}<table>\n\
+             <tr>{}<th>Addresses</th><th>Line</th><th></th></tr>\n{}</table>\n",
+            escape(&path),
+            escape(title),
+            filter_form(profile),
+            headers,
+            rows
+        );
+        pages.insert(name.clone(), page(&path, tab_width, &body));
+
+        index.push_str(&format!(
+            "<tr data-cp=\"{}\"><td><a href=\"{}\">{}</a></td><td class=\"num\">{}</td>",
+            data_checkpoints(counts.keys()),
+            name,
+            escape(&path),
+            lines
+        ));
+        for id in profile.checkpoints.keys() {
+            index.push_str(&format!(
+                "<td class=\"num\">{}</td>",
+                counts.get(id).cloned().unwrap_or(0)
+            ));
+        }
+        index.push_str("</tr>\n");
+    }
+
+    let body = format!(
+        "<h1>{}</h1>\n{}<table>\n<tr><th>File</th><th>Lines</th>{}</tr>\n{}</table>\n",
+        escape(title),
+        filter_form(profile),
+        headers,
+        index
+    );
+    pages.insert(String::from("index.html"), page(title, tab_width, &body));
+
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, report};
+    use crate::model::profile::Profile;
+
+    #[test]
+    fn write_report() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516").synced();
+        let pages = report(&profile, "hello <1>", 4);
+
+        let names: Vec<&str> = pages.keys().map(|name| name.as_str()).collect();
+        assert_eq!(names, vec!["file-1.html", "index.html"]);
+
+        let index = &pages["index.html"];
+        assert!(index.contains("<title>hello &lt;1&gt;</title>"));
+        assert!(index.contains("<span>Before_hello</span>"));
+        assert!(index.contains(
+            "<tr data-cp=\"0 1\"><td><a href=\"file-1.html\">assets/test/hello/hello.c</a>\
+             </td><td class=\"num\">5</td><td class=\"num\">3</td><td class=\"num\">3</td></tr>"
+        ));
+        assert!(!index.contains("http"));
+
+        let file = &pages["file-1.html"];
+        assert!(file.contains(
+            "<tr data-cp=\"0 1\"><td class=\"m\">◼</td><td class=\"m\">◼</td>\
+             <td class=\"addr\">0x1089d1-0x108a29</td><td class=\"nb\">13</td>"
+        ));
+        assert!(file.contains(
+            "<tr data-cp=\"\" class=\"nocode\"><td></td><td></td><td class=\"addr\"></td>\
+             <td class=\"nb\">1</td>"
+        ));
+
+        // Reports are reproducible
+        assert_eq!(report(&profile, "hello <1>", 4), pages);
+
+        assert_eq!(escape("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+}
@@ -0,0 +1,151 @@
+//! Coverage of a checkpoint as an lcov tracefile, for genhtml and coverage services.
+//!
+//! Each line of the profile is a `DA:` record, executed once if it met the checkpoint
+//! and never otherwise. A function is executed if one of its lines is. Lines without
+//! debug information are left out, their line numbers being meaningless.
+//!
+//! ```text
+//! TN:Before_hello
+//! SF:assets/test/hello/hello.c
+//! FN:9,main
+//! FNDA:1,main
+//! FNF:1
+//! FNH:1
+//! DA:9,0
+//! DA:13,1
+//! LF:2
+//! LH:1
+//! end_of_record
+//! ```
+
+use std::collections::BTreeMap;
+
+use super::identifier;
+use crate::model::profile::{Profile, ProfileItem};
+
+#[derive(Default)]
+struct SourceFile {
+    /// First line and whether a line met the checkpoint, by function
+    functions: BTreeMap<String, (usize, bool)>,
+    /// Whether the line met the checkpoint, by line number
+    lines: BTreeMap<usize, bool>,
+}
+
+impl SourceFile {
+    fn record(&self, path: &str) -> String {
+        let mut record = format!("SF:{}\n", path);
+
+        for (name, (nb, _)) in self.functions.iter() {
+            record.push_str(&format!("FN:{},{}\n", nb, name));
+        }
+        for (name, (_, met)) in self.functions.iter() {
+            record.push_str(&format!("FNDA:{},{}\n", *met as u8, name));
+        }
+        record.push_str(&format!("FNF:{}\n", self.functions.len()));
+        record.push_str(&format!(
+            "FNH:{}\n",
+            self.functions.values().filter(|(_, met)| *met).count()
+        ));
+
+        for (nb, met) in self.lines.iter() {
+            record.push_str(&format!("DA:{},{}\n", nb, *met as u8));
+        }
+        record.push_str(&format!("LF:{}\n", self.lines.len()));
+        record.push_str(&format!(
+            "LH:{}\n",
+            self.lines.values().filter(|met| **met).count()
+        ));
+
+        record.push_str("end_of_record\n");
+        record
+    }
+}
+
+/// The lines executed in a checkpoint, as an lcov tracefile
+pub fn tracefile(profile: &Profile, checkpoint: u32) -> String {
+    let name = match profile.checkpoint_name(checkpoint) {
+        Some(name) => String::from(name),
+        None => checkpoint.to_string(),
+    };
+    let mut text = format!("TN:{}\n", identifier(&name));
+    let mut files: Vec<(String, SourceFile)> = vec![];
+
+    for item in profile.items.iter() {
+        match item {
+            ProfileItem::File(f) if f.borrow().has_debug_info => {
+                let path = f.borrow().path.expand().display().to_string();
+                files.push((path, SourceFile::default()));
+            }
+            ProfileItem::File(_) => {}
+            ProfileItem::Line(_, l) if !l.has_debug_info || l.nb == 0 => {}
+            ProfileItem::Line(f, l) => {
+                let file = match files.last_mut() {
+                    Some((_, file)) if f.borrow().has_debug_info => file,
+                    _ => continue,
+                };
+                let met = l.checkpoints.contains(&checkpoint);
+
+                *file.lines.entry(l.nb).or_default() |= met;
+
+                if let Some(function) = &l.function {
+                    let entry = file
+                        .functions
+                        .entry(function.to_string())
+                        .or_insert((l.nb, false));
+
+                    entry.0 = entry.0.min(l.nb);
+                    entry.1 |= met;
+                }
+            }
+        }
+    }
+
+    for (path, file) in files.iter() {
+        if !file.lines.is_empty() {
+            text.push_str(&file.record(path));
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tracefile;
+    use crate::model::profile::Profile;
+
+    #[test]
+    fn write_tracefile() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+
+        assert_eq!(
+            tracefile(&profile, 0),
+            "TN:memviz_begin\n\
+             SF:assets/test/hello/hello.c\n\
+             FN:9,main\n\
+             FNDA:1,main\n\
+             FNF:1\n\
+             FNH:1\n\
+             DA:9,1\n\
+             DA:11,1\n\
+             DA:13,1\n\
+             DA:15,0\n\
+             DA:19,0\n\
+             LF:5\n\
+             LH:3\n\
+             end_of_record\n"
+        );
+
+        let text = tracefile(&profile, 1);
+        assert!(text.contains("DA:9,0\n"));
+        assert!(text.contains("DA:19,1\n"));
+
+        // A checkpoint met by no line
+        let mut profile = profile;
+        profile.checkpoints.insert(7, String::from("unmet"));
+        let text = tracefile(&profile, 7);
+        assert!(text.starts_with("TN:unmet\n"));
+        assert!(text.contains("FNDA:0,main\nFNF:1\nFNH:0\n"));
+        assert!(text.contains("LH:0\n"));
+    }
+}
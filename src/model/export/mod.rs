@@ -0,0 +1,153 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::profile::{PathInfo, Profile, ProfileItem};
+
+pub mod callgrind;
+pub mod csv;
+pub mod html;
+pub mod lcov;
+pub mod svg;
+
+/// A checkpoint name made of letters, digits and underscores only, as test names of
+/// lcov and event names of callgrind
+pub fn identifier(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// What a row of an exported table stands for
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Aggregation {
+    /// A line of the profile
+    Line,
+    /// The lines of a function, in a file
+    Function,
+    /// The lines of a file
+    File,
+}
+
+/// A line of a profile, or the lines of a function or of a file
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Row {
+    /// File of the lines
+    pub path: PathInfo,
+    /// Function of the lines, `None` when unknown or when aggregating per file
+    pub function: Option<String>,
+    /// Number of the first line
+    pub nb: usize,
+    /// Lowest first and highest last instruction address of the lines with code,
+    /// `(0, 0)` if there are none
+    pub addr_range: (u64, u64),
+    /// Sum of the sizes of the address ranges of the lines
+    pub size: u64,
+    /// Number of lines
+    pub lines: usize,
+    /// Number of lines that met each checkpoint, indexed by checkpoint id
+    pub counts: BTreeMap<u32, usize>,
+}
+
+impl Row {
+    fn add(&mut self, nb: usize, addr_range: (u64, u64), checkpoints: &BTreeSet<u32>) {
+        if addr_range != (0, 0) {
+            self.addr_range = match self.addr_range {
+                (0, 0) => addr_range,
+                (start, end) => (start.min(addr_range.0), end.max(addr_range.1)),
+            };
+        }
+
+        self.nb = self.nb.min(nb);
+        self.size += addr_range.1 - addr_range.0;
+        self.lines += 1;
+
+        for id in checkpoints {
+            *self.counts.entry(*id).or_default() += 1;
+        }
+    }
+}
+
+/// The lines of the profile, aggregated per function or per file, in the order of
+/// the profile. Files without lines are left out.
+pub fn rows(profile: &Profile, aggregation: Aggregation) -> Vec<Row> {
+    let mut rows: Vec<Row> = vec![];
+    // Aggregated rows of the current file, by function
+    let mut file_rows: BTreeMap<Option<String>, usize> = BTreeMap::new();
+
+    for item in profile.items.iter() {
+        let (file, line) = match item {
+            ProfileItem::File(_) => {
+                file_rows.clear();
+                continue;
+            }
+            ProfileItem::Line(f, l) => (f.borrow(), l),
+        };
+
+        let function = match aggregation {
+            Aggregation::File => None,
+            _ => line.function.as_ref().map(|f| f.to_string()),
+        };
+
+        let index = match (aggregation, file_rows.get(&function)) {
+            (Aggregation::Line, _) | (_, None) => {
+                rows.push(Row {
+                    path: file.path.clone(),
+                    function: function.clone(),
+                    nb: line.nb,
+                    addr_range: (0, 0),
+                    size: 0,
+                    lines: 0,
+                    counts: BTreeMap::new(),
+                });
+                file_rows.insert(function, rows.len() - 1);
+                rows.len() - 1
+            }
+            (_, Some(index)) => *index,
+        };
+
+        rows[index].add(line.nb, line.addr_range, &line.checkpoints);
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{identifier, rows, Aggregation};
+    use crate::model::profile::Profile;
+    use std::collections::BTreeMap;
+
+    fn counts(counts: &[(u32, usize)]) -> BTreeMap<u32, usize> {
+        counts.iter().cloned().collect()
+    }
+
+    #[test]
+    fn aggregate_rows() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+
+        let lines = rows(&profile, Aggregation::Line);
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[2].nb, 13);
+        assert_eq!(lines[2].addr_range, (0x1089d1, 0x108a29));
+        assert_eq!(lines[2].size, 0x108a29 - 0x1089d1);
+        assert_eq!(lines[2].counts, counts(&[(0, 1), (1, 1)]));
+        assert_eq!(lines[3].counts, counts(&[(1, 1)]));
+
+        let functions = rows(&profile, Aggregation::Function);
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].function.as_deref(), Some("main"));
+        assert_eq!(functions[0].nb, 9);
+        assert_eq!(functions[0].addr_range, (0x1089ac, 0x108a55));
+        assert_eq!(functions[0].lines, 5);
+        assert_eq!(functions[0].counts, counts(&[(0, 3), (1, 3)]));
+
+        let files = rows(&profile, Aggregation::File);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].function, None);
+        assert_eq!(files[0].size, functions[0].size);
+    }
+
+    #[test]
+    fn identifiers() {
+        assert_eq!(identifier("Before hello-2"), "Before_hello_2");
+    }
+}
@@ -0,0 +1,139 @@
+//! The checkpoint matrix as an SVG heatmap, for papers and slides.
+//!
+//! Rows are lines, functions or files, and columns are checkpoints. A cell is coloured
+//! by the share of the lines of its row that met its checkpoint, from light grey (none)
+//! to dark green (all). The output only depends on the profile, so that it can be
+//! compared with a reference.
+
+use super::{html::escape, rows, Aggregation, Row};
+use crate::model::profile::Profile;
+
+const CELL: usize = 14;
+/// Width of a character of the 11px monospace font
+const CHAR_WIDTH: usize = 7;
+const MARGIN: usize = 8;
+
+/// Colour of a cell, where `share` is in [0, 1]
+fn color(share: f64) -> String {
+    if share <= 0.0 {
+        return String::from("#eeeeee");
+    }
+
+    // From light to dark green
+    let light = [198.0, 228.0, 139.0];
+    let dark = [25.0, 97.0, 39.0];
+    let channel = |i: usize| (light[i] + (dark[i] - light[i]) * share.min(1.0)).round() as u8;
+
+    format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2))
+}
+
+fn label(row: &Row, aggregation: Aggregation) -> String {
+    let path = row.path.expand().display().to_string();
+
+    match (aggregation, &row.function) {
+        (Aggregation::Line, Some(function)) => format!("{}:{} ({})", path, row.nb, function),
+        (Aggregation::Line, None) => format!("{}:{}", path, row.nb),
+        (Aggregation::Function, Some(function)) => format!("{} ({})", function, path),
+        _ => path,
+    }
+}
+
+/// The heatmap of the checkpoints met by the lines, functions or files
+pub fn heatmap(profile: &Profile, aggregation: Aggregation) -> String {
+    let rows = rows(profile, aggregation);
+    let labels: Vec<String> = rows.iter().map(|row| label(row, aggregation)).collect();
+
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) * CHAR_WIDTH;
+    let header_height = profile
+        .checkpoints
+        .values()
+        .map(|name| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        * CHAR_WIDTH;
+
+    let left = MARGIN + label_width + MARGIN;
+    let top = MARGIN + header_height + MARGIN;
+    let width = left + profile.checkpoints.len() * CELL + MARGIN;
+    let height = top + rows.len() * CELL + MARGIN;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         viewBox=\"0 0 {} {}\" font-family=\"monospace\" font-size=\"11\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>\n",
+        width, height, width, height
+    );
+
+    for (column, name) in profile.checkpoints.values().enumerate() {
+        let x = left + column * CELL + CELL / 2 + 4;
+        svg.push_str(&format!(
+            "<text transform=\"translate({},{}) rotate(-90)\">{}</text>\n",
+            x,
+            top - MARGIN,
+            escape(name)
+        ));
+    }
+
+    for (i, (row, label)) in rows.iter().zip(labels.iter()).enumerate() {
+        let y = top + i * CELL;
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n",
+            left - MARGIN,
+            y + CELL - 3,
+            escape(label)
+        ));
+
+        for (column, (id, name)) in profile.checkpoints.iter().enumerate() {
+            let count = row.counts.get(id).cloned().unwrap_or(0);
+            let share = count as f64 / row.lines.max(1) as f64;
+
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" \
+                 stroke=\"#ffffff\"><title>{} {}: {}/{}</title></rect>\n",
+                left + column * CELL,
+                y,
+                CELL,
+                CELL,
+                color(share),
+                escape(label),
+                escape(name),
+                count,
+                row.lines
+            ));
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{color, heatmap};
+    use crate::model::{export::Aggregation, profile::Profile};
+
+    #[test]
+    fn draw_heatmap() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+
+        let lines = heatmap(&profile, Aggregation::Line);
+        assert!(lines.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(lines.ends_with("</svg>\n"));
+        assert_eq!(lines.matches("<rect x=").count(), 5 * 2);
+        assert!(lines.contains(">assets/test/hello/hello.c:13 (main)</text>"));
+        assert!(lines.contains("rotate(-90)\">Before_hello</text>"));
+        assert!(lines.contains(
+            "fill=\"#196127\" stroke=\"#ffffff\"><title>assets/test/hello/hello.c:9 (main) \
+             memviz_begin: 1/1</title>"
+        ));
+        assert_eq!(heatmap(&profile, Aggregation::Line), lines);
+
+        let functions = heatmap(&profile, Aggregation::Function);
+        assert_eq!(functions.matches("<rect x=").count(), 2);
+        assert!(functions.contains(">main (assets/test/hello/hello.c)</text>"));
+        assert!(functions.contains(&format!("fill=\"{}\"", color(0.6))));
+
+        assert_eq!(color(0.0), "#eeeeee");
+        assert_eq!(color(1.0), "#196127");
+    }
+}
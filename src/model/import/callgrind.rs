@@ -0,0 +1,405 @@
+//! Lines executed in callgrind and cachegrind profiles.
+//!
+//! The costs of the lines are read from `fl=`, `fi=`, `fe=` and `fn=` lines (with name
+//! compression such as `fl=(1) main.c`, then `fl=(1)`) and position lines (with
+//! relative positions such as `+2` or `*`). The cost of a call is the one of its calling
+//! line. Object files (`ob=`) and the other headers are ignored.
+//!
+//! A profile made of a single part has a checkpoint per event (`Ir`, `Dr`...), met by
+//! the lines with a cost for this event. Otherwise, each part of the dumps (a file, or a
+//! `part:` of a file) is a checkpoint, met by the lines with a cost for any event.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use super::ProfileBuilder;
+use crate::model::profile::{CodeLoc, Profile};
+
+/// A line: its file, function and number
+type LineId = (String, String, usize);
+
+/// The costs of a dump, or of a part of a dump
+struct Part {
+    name: String,
+    events: Vec<String>,
+    /// Address range, and whether it has a cost, for each event
+    lines: BTreeMap<LineId, ((u64, u64), Vec<bool>)>,
+    /// Lines in the order of the dump, so that the first function of an inlined line
+    /// is the one kept
+    order: Vec<LineId>,
+}
+
+/// Names declared with name compression, by id
+#[derive(Default)]
+struct Names(BTreeMap<String, String>);
+
+impl Names {
+    /// The name of `(ID) NAME`, `(ID)` or `NAME`
+    fn resolve(&mut self, value: &str) -> Result<String, String> {
+        let value = value.trim();
+
+        let (id, name) = match value.strip_prefix('(').and_then(|v| v.split_once(')')) {
+            Some((id, name)) => (id, name.trim()),
+            None => return Ok(String::from(value)),
+        };
+
+        if !name.is_empty() {
+            self.0.insert(String::from(id), String::from(name));
+            return Ok(String::from(name));
+        }
+
+        self.0
+            .get(id)
+            .cloned()
+            .ok_or_else(|| format!("undefined name ({})", id))
+    }
+}
+
+/// Parse a decimal or `0x` hexadecimal number
+fn parse_number(s: &str) -> Result<u64, String> {
+    let number = match s.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+
+    number.map_err(|_| format!("invalid number {:?}", s))
+}
+
+/// Parse a position, absolute or relative to the previous one
+fn parse_position(s: &str, previous: u64) -> Result<u64, String> {
+    if s == "*" {
+        Ok(previous)
+    } else if let Some(offset) = s.strip_prefix('+') {
+        Ok(previous.wrapping_add(parse_number(offset)?))
+    } else if let Some(offset) = s.strip_prefix('-') {
+        Ok(previous.wrapping_sub(parse_number(offset)?))
+    } else {
+        parse_number(s)
+    }
+}
+
+/// State of the reader of a dump
+struct Reader {
+    parts: Vec<Part>,
+    name: String,
+    positions: Vec<String>,
+    events: Vec<String>,
+    files: Names,
+    functions: Names,
+    /// Current file, changed by `fl=`, `fi=` and `fe=`
+    file: Option<String>,
+    function: Option<String>,
+    /// Previous position, for relative positions
+    position: Vec<u64>,
+}
+
+impl Reader {
+    fn new(name: &str) -> Reader {
+        Reader {
+            parts: vec![],
+            name: String::from(name),
+            positions: vec![String::from("line")],
+            events: vec![],
+            files: Names::default(),
+            functions: Names::default(),
+            file: None,
+            function: None,
+            position: vec![0],
+        }
+    }
+
+    fn part(&mut self) -> &mut Part {
+        if self.parts.is_empty() {
+            self.new_part(None);
+        }
+
+        self.parts.last_mut().unwrap()
+    }
+
+    fn new_part(&mut self, number: Option<&str>) {
+        let name = match number {
+            Some(number) => format!("{} part {}", self.name, number),
+            None => self.name.clone(),
+        };
+
+        self.parts.push(Part {
+            name,
+            events: self.events.clone(),
+            lines: BTreeMap::new(),
+            order: vec![],
+        });
+    }
+
+    fn read_header(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "positions" => {
+                self.positions = value.split_whitespace().map(String::from).collect();
+                self.position = vec![0; self.positions.len()];
+
+                if !self.positions.iter().all(|p| p == "line" || p == "instr") {
+                    return Err(format!("unsupported positions {:?}", value));
+                }
+            }
+            "events" => {
+                self.events = value.split_whitespace().map(String::from).collect();
+
+                if let Some(part) = self.parts.last_mut() {
+                    if part.lines.is_empty() {
+                        part.events = self.events.clone();
+                    }
+                }
+            }
+            "part" => match self.parts.last() {
+                // A part header before any cost names the first part
+                Some(part) if part.lines.is_empty() && self.parts.len() == 1 => {
+                    self.parts[0].name = format!("{} part {}", self.name, value.trim());
+                }
+                _ => self.new_part(Some(value.trim())),
+            },
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn read_cost(&mut self, line: &str) -> Result<(), String> {
+        let mut fields = line.split_whitespace();
+
+        for (previous, kind) in self.position.iter_mut().zip(self.positions.iter()) {
+            let field = fields.next().ok_or_else(|| format!("missing {}", kind))?;
+            *previous = parse_position(field, *previous)?;
+        }
+
+        let costs: Vec<bool> = fields
+            .map(|cost| parse_number(cost).map(|cost| cost > 0))
+            .collect::<Result<_, _>>()?;
+
+        let nb = self.positions.iter().position(|p| p == "line");
+        let instr = self.positions.iter().position(|p| p == "instr");
+        let nb = nb.map_or(0, |i| self.position[i] as usize);
+        let addr = instr.map_or(0, |i| self.position[i]);
+
+        let file = match &self.file {
+            Some(file) => file.clone(),
+            None => return Err(String::from("cost line before any fl= line")),
+        };
+        let function = self.function.clone().unwrap_or_else(|| String::from("???"));
+
+        let part = self.part();
+        let events = part.events.len().max(costs.len());
+        let id = (file, function, nb);
+        if !part.lines.contains_key(&id) {
+            part.order.push(id.clone());
+        }
+        let (addr_range, met) = part.lines.entry(id).or_insert(((addr, addr), vec![]));
+
+        *addr_range = (addr_range.0.min(addr), addr_range.1.max(addr));
+        met.resize(events.max(met.len()), false);
+        for (m, cost) in met.iter_mut().zip(costs) {
+            *m |= cost;
+        }
+
+        Ok(())
+    }
+
+    fn read_line(&mut self, line: &str) -> Result<(), String> {
+        if line.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-' || c == '*') {
+            return self.read_cost(line);
+        }
+
+        if let Some((key, value)) = line.split_once('=') {
+            match key {
+                "fl" | "fi" | "fe" => {
+                    self.file = Some(self.files.resolve(value)?);
+                }
+                "fn" => {
+                    self.function = Some(self.functions.resolve(value)?);
+                }
+                // The names of called files and functions may be declared here
+                "cfi" | "cfl" => {
+                    self.files.resolve(value)?;
+                }
+                "cfn" => {
+                    self.functions.resolve(value)?;
+                }
+                _ => {}
+            }
+
+            return Ok(());
+        }
+
+        match line.split_once(':') {
+            Some((key, value)) => self.read_header(key.trim(), value),
+            None => Err(format!("unexpected line {:?}", line)),
+        }
+    }
+}
+
+/// Read the parts of a dump, named after `name`
+fn read_parts(text: &str, name: &str) -> Result<Vec<Part>, String> {
+    let mut reader = Reader::new(name);
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        reader
+            .read_line(line)
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+    }
+
+    Ok(reader.parts)
+}
+
+fn add_parts(builder: &mut ProfileBuilder, parts: Vec<Part>) {
+    let per_event = parts.len() == 1;
+
+    for part in parts {
+        let checkpoints: Vec<u32> = match per_event {
+            true => part.events.iter().map(|e| builder.checkpoint(e)).collect(),
+            false => vec![builder.checkpoint(&part.name)],
+        };
+
+        let mut lines = part.lines;
+
+        for (file, function, nb) in part.order {
+            let (addr_range, met) = lines.remove(&(file.clone(), function.clone(), nb)).unwrap();
+            let checkpoints = match per_event {
+                true => met
+                    .iter()
+                    .zip(checkpoints.iter())
+                    .filter(|(met, _)| **met)
+                    .map(|(_, id)| *id)
+                    .collect(),
+                false if met.contains(&true) => bt_set!(checkpoints[0]),
+                false => bt_set!(),
+            };
+
+            builder.add(CodeLoc {
+                file,
+                function,
+                nb,
+                addr_range,
+                checkpoints,
+            });
+        }
+    }
+}
+
+/// Add the costs of a dump to the builder, see the module documentation
+pub fn read_dump(builder: &mut ProfileBuilder, text: &str, name: &str) -> Result<(), String> {
+    add_parts(builder, read_parts(text, name)?);
+    Ok(())
+}
+
+/// A profile of dumps, whose checkpoints are their events or their parts
+pub fn import<P: AsRef<Path>>(paths: &[P]) -> Result<Profile, String> {
+    let mut builder = ProfileBuilder::new();
+    let mut parts = vec![];
+
+    for path in paths {
+        let path = path.as_ref();
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        // Dumps are usually told apart by their extension: callgrind.out.PID.PART
+        let name = path
+            .file_name()
+            .map_or_else(|| PathBuf::from(path), PathBuf::from);
+
+        parts.extend(
+            read_parts(&text, &name.display().to_string())
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+        );
+    }
+
+    add_parts(&mut builder, parts);
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_dump;
+    use crate::model::{import::ProfileBuilder, profile::ProfileItem};
+
+    fn lines(builder: ProfileBuilder) -> Vec<String> {
+        builder
+            .build()
+            .items
+            .iter()
+            .map(|item| match item {
+                ProfileItem::File(f) => f.borrow().path.file.clone(),
+                ProfileItem::Line(_, l) => format!(
+                    "{} {} {:x?} {:?}",
+                    l.nb,
+                    l.function.as_ref().unwrap(),
+                    l.addr_range,
+                    l.checkpoints
+                ),
+            })
+            .collect()
+    }
+
+    const DUMP: &str = "# callgrind format\n\
+                        version: 1\n\
+                        positions: instr line\n\
+                        events: Ir Dr\n\
+                        \n\
+                        fl=(1) /src/main.c\n\
+                        fn=(1) main\n\
+                        0x1000 3 4 1\n\
+                        +4 * 2\n\
+                        +8 +2 1 0\n\
+                        cfn=(2) helper\n\
+                        calls=1 0x2000 10\n\
+                        * * 12 3\n\
+                        fl=(2) ???\n\
+                        fn=(3) _start\n\
+                        0x500 0 1\n\
+                        fl=(1)\n\
+                        fn=(2)\n\
+                        0x2000 10 6 0\n";
+
+    #[test]
+    fn read_dumps() {
+        let mut builder = ProfileBuilder::new();
+        read_dump(&mut builder, DUMP, "callgrind.out").unwrap();
+        assert_eq!(builder.checkpoint("Dr"), 1);
+        assert_eq!(
+            lines(builder),
+            vec![
+                "???",
+                "0 _start (500, 500) {0}",
+                "/src/main.c",
+                "3 main (1000, 1004) {0, 1}",
+                "5 main (100c, 100c) {0, 1}",
+                "10 helper (2000, 2000) {0}"
+            ]
+        );
+
+        // Each part is a checkpoint
+        let mut builder = ProfileBuilder::new();
+        let parts = "events: Ir\nfl=a.c\nfn=f\n1 1\n2 0\npart: 2\n2 3\n";
+        read_dump(&mut builder, &format!("part: 1\n{}", parts), "dump").unwrap();
+        assert_eq!(builder.checkpoint("dump part 2"), 1);
+        assert_eq!(
+            lines(builder),
+            vec!["a.c", "1 f (0, 0) {0}", "2 f (0, 0) {1}"]
+        );
+
+        // A line of a header inlined in several functions
+        let mut builder = ProfileBuilder::new();
+        let inlined = "events: Ir Dr\nfl=h.h\nfn=foo\n10 1 0\nfn=bar\n10 0 1\n";
+        read_dump(&mut builder, inlined, "dump").unwrap();
+        assert_eq!(lines(builder), vec!["h.h", "10 foo (0, 0) {0, 1}"]);
+
+        let mut builder = ProfileBuilder::new();
+        assert!(read_dump(&mut builder, "events: Ir\n1 1\n", "dump").is_err());
+        assert!(read_dump(&mut builder, "fl=(1)\n", "dump").is_err());
+        assert!(read_dump(&mut builder, "fl=a.c\nx 1\n", "dump").is_err());
+    }
+}
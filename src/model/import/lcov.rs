@@ -0,0 +1,262 @@
+//! Coverage of tests from lcov tracefiles.
+//!
+//! Each test of a tracefile (`TN:` record) is a checkpoint, met by the lines it executed.
+//! Tracefiles without test name are named after the file. The lines (`DA:` records)
+//! belong to the function (`FN:` records, or `FNL:`/`FNA:` for lcov 2.2) starting
+//! closest before them.
+
+use std::{collections::BTreeMap, path::Path};
+
+use super::{file_checkpoint_name, ProfileBuilder};
+use crate::model::profile::{CodeLoc, Profile};
+
+/// A `SF:` record, up to its `end_of_record`
+#[derive(Default)]
+struct SourceFile {
+    path: String,
+    /// Function names, by first line
+    functions: BTreeMap<usize, String>,
+    /// Functions of lcov 2.2, by index: first line and name
+    indexed_functions: BTreeMap<String, (Option<usize>, Option<String>)>,
+    /// Execution counts, by line
+    lines: BTreeMap<usize, u64>,
+}
+
+impl SourceFile {
+    fn add_to(self, builder: &mut ProfileBuilder, checkpoint: u32) {
+        let mut functions = self.functions;
+
+        for (nb, name) in self.indexed_functions.into_values() {
+            if let (Some(nb), Some(name)) = (nb, name) {
+                functions.insert(nb, name);
+            }
+        }
+
+        for (nb, count) in self.lines {
+            let function = match functions.range(..=nb).next_back() {
+                Some((_, name)) => name.clone(),
+                None => String::from("???"),
+            };
+
+            builder.add(CodeLoc {
+                file: self.path.clone(),
+                function,
+                nb,
+                addr_range: (0, 0),
+                checkpoints: match count {
+                    0 => bt_set!(),
+                    _ => bt_set!(checkpoint),
+                },
+            });
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.trim()
+        .parse()
+        .map_err(|_| format!("invalid number {:?}", s))
+}
+
+/// Execution counts are integers, but some tools write them as floats, or negative
+fn parse_count(s: &str) -> Result<u64, String> {
+    s.trim()
+        .parse::<f64>()
+        .map(|count| if count > 0.0 { count.ceil() as u64 } else { 0 })
+        .map_err(|_| format!("invalid count {:?}", s))
+}
+
+fn parse_record(
+    builder: &mut ProfileBuilder,
+    file: &mut Option<SourceFile>,
+    checkpoint: &mut Option<u32>,
+    default_name: &str,
+    line: &str,
+) -> Result<(), String> {
+    let (key, value) = match line.split_once(':') {
+        Some(record) => record,
+        None if line == "end_of_record" => {
+            let id = *checkpoint.get_or_insert_with(|| builder.checkpoint(default_name));
+
+            match file.take() {
+                Some(file) => file.add_to(builder, id),
+                None => return Err(String::from("end_of_record without SF: record")),
+            }
+            return Ok(());
+        }
+        None => return Err(format!("unexpected line {:?}", line)),
+    };
+
+    if key == "TN" {
+        let name = if value.is_empty() {
+            default_name
+        } else {
+            value
+        };
+        *checkpoint = Some(builder.checkpoint(name));
+        return Ok(());
+    }
+
+    if key == "SF" {
+        *file = Some(SourceFile {
+            path: String::from(value),
+            ..SourceFile::default()
+        });
+        return Ok(());
+    }
+
+    let file = match file {
+        Some(file) => file,
+        None if ["FN", "FNL", "FNA", "DA"].contains(&key) => {
+            return Err(format!("{} record outside of a SF: record", key))
+        }
+        // Other records do not matter, wherever they are
+        None => return Ok(()),
+    };
+    let fields: Vec<&str> = value.splitn(3, ',').collect();
+
+    match (key, fields.as_slice()) {
+        // FN:LINE,NAME, or FN:LINE,END,NAME since lcov 2.0
+        ("FN", [nb, end, name]) if end.parse::<usize>().is_ok() => {
+            file.functions
+                .insert(parse_number(nb)?, String::from(*name));
+        }
+        ("FN", [nb, ..]) => {
+            let name = &value[nb.len() + 1..];
+            file.functions.insert(parse_number(nb)?, String::from(name));
+        }
+        // FNL:INDEX,LINE[,END]
+        ("FNL", [index, nb, ..]) => {
+            let function = file.indexed_functions.entry(index.to_string()).or_default();
+            function.0 = Some(parse_number(nb)?);
+        }
+        // FNA:INDEX,COUNT,NAME
+        ("FNA", [index, _, name]) => {
+            let function = file.indexed_functions.entry(index.to_string()).or_default();
+            function.1 = Some(String::from(*name));
+        }
+        // DA:LINE,COUNT[,CHECKSUM]
+        ("DA", [nb, count, ..]) => {
+            *file.lines.entry(parse_number(nb)?).or_default() += parse_count(count)?;
+        }
+        ("FN", _) | ("FNL", _) | ("FNA", _) | ("DA", _) => {
+            return Err(format!("invalid {} record {:?}", key, line))
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Add the tests of a tracefile to the builder. `default_name` names the checkpoint
+/// of the lines before any `TN:` record.
+pub fn read_tracefile(
+    builder: &mut ProfileBuilder,
+    text: &str,
+    default_name: &str,
+) -> Result<(), String> {
+    let mut file = None;
+    let mut checkpoint = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        parse_record(builder, &mut file, &mut checkpoint, default_name, line)
+            .map_err(|e| format!("line {}: {}", i + 1, e))?;
+    }
+
+    match file {
+        Some(_) => Err(String::from("missing end_of_record")),
+        None => Ok(()),
+    }
+}
+
+/// A profile whose checkpoints are the tests of the tracefiles
+pub fn import<P: AsRef<Path>>(paths: &[P]) -> Result<Profile, String> {
+    let mut builder = ProfileBuilder::new();
+
+    for path in paths {
+        let path = path.as_ref();
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        read_tracefile(&mut builder, &text, &file_checkpoint_name(path))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_tracefile;
+    use crate::model::{import::ProfileBuilder, profile::ProfileItem};
+
+    fn lines(builder: ProfileBuilder) -> Vec<String> {
+        builder
+            .build()
+            .items
+            .iter()
+            .map(|item| match item {
+                ProfileItem::File(f) => f.borrow().path.file.clone(),
+                ProfileItem::Line(_, l) => format!(
+                    "{} {} {:?}",
+                    l.nb,
+                    l.function.as_ref().unwrap(),
+                    l.checkpoints
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn read_tracefiles() {
+        let mut builder = ProfileBuilder::new();
+
+        let first = "TN:\nSF:/src/main.c\nFN:3,main\nFN:10,12,f\nFNDA:1,main\nDA:3,1\n\
+                     DA:4,0\nDA:11,2,checksum\nLF:3\nLH:2\nend_of_record\n";
+        read_tracefile(&mut builder, first, "unit").unwrap();
+
+        let second = "TN:integration\nSF:/src/main.c\nFNL:0,3,5\nFNA:0,1,main\nDA:4,3\n\
+                      end_of_record\nSF:/src/lib.c\nDA:1,0\nend_of_record\n";
+        read_tracefile(&mut builder, second, "other").unwrap();
+
+        assert_eq!(builder.checkpoint("integration"), 1);
+        assert_eq!(
+            lines(builder),
+            vec![
+                "/src/lib.c",
+                "1 ??? {}",
+                "/src/main.c",
+                "3 main {0}",
+                "4 main {1}",
+                "11 f {0}"
+            ]
+        );
+
+        let mut builder = ProfileBuilder::new();
+        assert!(read_tracefile(&mut builder, "DA:1,1\n", "a").is_err());
+        assert!(read_tracefile(&mut builder, "SF:a.c\nDA:x,1\nend_of_record\n", "a").is_err());
+        assert!(read_tracefile(&mut builder, "SF:a.c\nDA:1,1\n", "a").is_err());
+    }
+
+    #[test]
+    fn merge_functions() {
+        // Only one tracefile knows the function of line 4
+        let mut builder = ProfileBuilder::new();
+        let first = "TN:a\nSF:/m.c\nFN:3,main\nDA:4,1\nend_of_record\n";
+        read_tracefile(&mut builder, first, "a").unwrap();
+        let second = "TN:b\nSF:/m.c\nDA:4,1\nend_of_record\n";
+        read_tracefile(&mut builder, second, "b").unwrap();
+        assert_eq!(lines(builder), vec!["/m.c", "4 main {0, 1}"]);
+
+        let mut builder = ProfileBuilder::new();
+        read_tracefile(&mut builder, second, "b").unwrap();
+        read_tracefile(&mut builder, first, "a").unwrap();
+        assert_eq!(lines(builder), vec!["/m.c", "4 main {0, 1}"]);
+    }
+}
@@ -0,0 +1,285 @@
+//! Coverage of tests from `llvm-cov export` JSON files.
+//!
+//! Each export is a checkpoint named after its file, or, when it holds several data
+//! entries, each entry is a checkpoint named `FILE.INDEX`. The lines executed are
+//! computed from the coverage segments the way `llvm-cov` does, and belong to the
+//! innermost function whose code regions span them.
+
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+use super::{file_checkpoint_name, ProfileBuilder};
+use crate::model::profile::{CodeLoc, Profile};
+
+#[derive(Deserialize)]
+struct Export {
+    data: Vec<Data>,
+}
+
+#[derive(Deserialize)]
+struct Data {
+    files: Vec<File>,
+    #[serde(default)]
+    functions: Vec<Function>,
+}
+
+#[derive(Deserialize)]
+struct File {
+    filename: String,
+    #[serde(default)]
+    segments: Vec<Vec<serde_json::Value>>,
+}
+
+#[derive(Deserialize)]
+struct Function {
+    name: String,
+    /// `[line_start, column_start, line_end, column_end, count, file_id,
+    /// expanded_file_id, kind]`
+    regions: Vec<Vec<u64>>,
+    filenames: Vec<String>,
+}
+
+/// `[line, column, count, has_count, is_region_entry, is_gap_region]`, the last field
+/// being missing in old exports
+struct Segment {
+    line: usize,
+    count: u64,
+    has_count: bool,
+    is_region_entry: bool,
+    is_gap_region: bool,
+}
+
+impl Segment {
+    fn new(fields: &[serde_json::Value]) -> Result<Segment, String> {
+        let number = |i: usize| fields.get(i).and_then(|f| f.as_u64());
+        let flag = |i: usize| fields.get(i).and_then(|f| f.as_bool());
+
+        match (number(0), number(2), flag(3), flag(4)) {
+            (Some(line), Some(count), Some(has_count), Some(is_region_entry)) => Ok(Segment {
+                line: line as usize,
+                count,
+                has_count,
+                is_region_entry,
+                is_gap_region: flag(5).unwrap_or(false),
+            }),
+            _ => Err(format!("invalid segment {:?}", fields)),
+        }
+    }
+
+    fn is_start_of_region(&self) -> bool {
+        !self.is_gap_region && self.has_count && self.is_region_entry
+    }
+}
+
+/// Execution count of the lines with code, like `LineCoverageStats` of llvm-cov
+fn line_counts(segments: &[Segment]) -> BTreeMap<usize, u64> {
+    let mut counts = BTreeMap::new();
+    let mut wrapped: Option<&Segment> = None;
+    let mut next = 0;
+    let last_line = segments.last().map_or(0, |s| s.line);
+
+    for line in 1..=last_line {
+        let start = next;
+        while next < segments.len() && segments[next].line == line {
+            next += 1;
+        }
+        let line_segments = &segments[start..next];
+
+        let region_starts = line_segments
+            .iter()
+            .filter(|s| s.is_start_of_region())
+            .count();
+        let start_of_skipped_region = line_segments
+            .first()
+            .is_some_and(|s| !s.has_count && s.is_region_entry);
+        let mapped =
+            !start_of_skipped_region && (wrapped.is_some_and(|s| s.has_count) || region_starts > 0);
+
+        if mapped {
+            let mut count = wrapped.map_or(0, |s| s.count);
+
+            for s in line_segments.iter().filter(|s| s.is_start_of_region()) {
+                count = count.max(s.count);
+            }
+            counts.insert(line, count);
+        }
+
+        if let Some(last) = line_segments.last() {
+            wrapped = Some(last);
+        }
+    }
+
+    counts
+}
+
+/// Line ranges of the functions, by file
+fn function_ranges(functions: &[Function]) -> BTreeMap<&str, Vec<(usize, usize, &str)>> {
+    let mut ranges: BTreeMap<&str, Vec<(usize, usize, &str)>> = BTreeMap::new();
+
+    for function in functions {
+        // Code regions of the function itself, not of the macros it expands
+        let lines = function
+            .regions
+            .iter()
+            .filter(|r| r.len() >= 8 && r[5] == 0 && r[7] == 0)
+            .map(|r| (r[0] as usize, r[2] as usize));
+        let start = lines.clone().map(|(start, _)| start).min();
+        let end = lines.map(|(_, end)| end).max();
+
+        if let (Some(start), Some(end), Some(file)) = (start, end, function.filenames.first()) {
+            ranges
+                .entry(file.as_str())
+                .or_default()
+                .push((start, end, function.name.as_str()));
+        }
+    }
+
+    ranges
+}
+
+fn read_data(builder: &mut ProfileBuilder, data: &Data, name: &str) -> Result<(), String> {
+    let checkpoint = builder.checkpoint(name);
+    let ranges = function_ranges(&data.functions);
+
+    for file in data.files.iter() {
+        let segments: Vec<Segment> = file
+            .segments
+            .iter()
+            .map(|s| Segment::new(s))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("{}: {}", file.filename, e))?;
+        let functions = ranges.get(file.filename.as_str());
+
+        for (nb, count) in line_counts(&segments) {
+            // The innermost function: the last one starting before the line
+            let function = functions
+                .and_then(|functions| {
+                    functions
+                        .iter()
+                        .filter(|(start, end, _)| *start <= nb && nb <= *end)
+                        .max_by_key(|(start, _, _)| *start)
+                })
+                .map_or("???", |(_, _, name)| name);
+
+            builder.add(CodeLoc {
+                file: file.filename.clone(),
+                function: String::from(function),
+                nb,
+                addr_range: (0, 0),
+                checkpoints: match count {
+                    0 => bt_set!(),
+                    _ => bt_set!(checkpoint),
+                },
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Add the tests of an export to the builder, named after `name`
+pub fn read_export(builder: &mut ProfileBuilder, json: &str, name: &str) -> Result<(), String> {
+    let export: Export = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+    match export.data.as_slice() {
+        [data] => read_data(builder, data, name),
+        entries => {
+            for (i, data) in entries.iter().enumerate() {
+                read_data(builder, data, &format!("{}.{}", name, i))?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// A profile whose checkpoints are the tests of the exports
+pub fn import<P: AsRef<Path>>(paths: &[P]) -> Result<Profile, String> {
+    let mut builder = ProfileBuilder::new();
+
+    for path in paths {
+        let path = path.as_ref();
+        let json =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+        read_export(&mut builder, &json, &file_checkpoint_name(path))
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    Ok(builder.build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::read_export;
+    use crate::model::{import::ProfileBuilder, profile::ProfileItem};
+
+    // main() spans lines 3 to 9, and the body of its `if`, from the end of line 5 to
+    // line 6, is not executed
+    const EXPORT: &str = r#"{
+        "type": "llvm.coverage.json.export",
+        "version": "2.0.1",
+        "data": [{
+            "files": [{
+                "filename": "/src/main.c",
+                "segments": [
+                    [3, 16, 1, true, true, false],
+                    [5, 9, 0, true, true, false],
+                    [6, 15, 1, true, false, false],
+                    [9, 2, 0, false, false, false]
+                ]
+            }],
+            "functions": [{
+                "name": "main",
+                "count": 1,
+                "regions": [[3, 16, 9, 2, 1, 0, 0, 0], [5, 9, 6, 15, 0, 0, 0, 0]],
+                "filenames": ["/src/main.c"]
+            }]
+        }]
+    }"#;
+
+    #[test]
+    fn read_exports() {
+        let mut builder = ProfileBuilder::new();
+        read_export(&mut builder, EXPORT, "test").unwrap();
+        read_export(
+            &mut builder,
+            &EXPORT.replace("[5, 9, 0,", "[5, 9, 2,"),
+            "other",
+        )
+        .unwrap();
+
+        let profile = builder.build();
+        assert_eq!(profile.checkpoint_name(1), Some("other"));
+
+        let lines: Vec<String> = profile
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ProfileItem::Line(_, l) => Some(format!(
+                    "{} {} {:?}",
+                    l.nb,
+                    l.function.as_ref().unwrap(),
+                    l.checkpoints
+                )),
+                ProfileItem::File(_) => None,
+            })
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "3 main {0, 1}",
+                "4 main {0, 1}",
+                "5 main {0, 1}",
+                "6 main {1}",
+                "7 main {0, 1}",
+                "8 main {0, 1}",
+                "9 main {0, 1}"
+            ]
+        );
+
+        let mut builder = ProfileBuilder::new();
+        assert!(read_export(&mut builder, "{}", "test").is_err());
+        assert!(read_export(&mut builder, &EXPORT.replace("[3, 16, 1,", "[3,"), "test").is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+};
+
+use super::profile::{CodeLoc, FileInfo, LineInfo, PathInfo, Profile, ProfileItem};
+
+pub mod callgrind;
+pub mod lcov;
+pub mod llvm_cov;
+
+/// A line: its file, number and, for files without debug information, function.
+/// Like in `LineInfo`, lines with debug information are told apart by number only.
+type LineId = (String, usize, Option<String>);
+
+/// What is known of a line
+struct Line {
+    function: String,
+    addr_range: (u64, u64),
+    checkpoints: BTreeSet<u32>,
+}
+
+/// Gathers the lines read by an importer, and makes a profile of them
+#[derive(Default)]
+pub struct ProfileBuilder {
+    checkpoints: BTreeMap<u32, String>,
+    lines: BTreeMap<LineId, Line>,
+}
+
+impl ProfileBuilder {
+    /// A builder without checkpoint nor line
+    pub fn new() -> ProfileBuilder {
+        ProfileBuilder::default()
+    }
+
+    /// The id of the checkpoint with this name, declared if needed
+    pub fn checkpoint(&mut self, name: &str) -> u32 {
+        if let Some((id, _)) = self.checkpoints.iter().find(|(_, n)| n.as_str() == name) {
+            return *id;
+        }
+
+        let id = self.checkpoints.len() as u32;
+        self.checkpoints.insert(id, String::from(name));
+        id
+    }
+
+    /// Add a line, or merge its address range and checkpoints with the ones already
+    /// known for it. A line of a file with debug information keeps the first known
+    /// name of its function, the inputs may disagree on it (inlined code, template
+    /// instances, missing function records).
+    pub fn add(&mut self, loc: CodeLoc) {
+        let function = loc.function;
+        let id = match loc.file.as_str() {
+            "???" => (loc.file, loc.nb, Some(function.clone())),
+            _ => (loc.file, loc.nb, None),
+        };
+
+        let line = self.lines.entry(id).or_insert_with(|| Line {
+            function: function.clone(),
+            addr_range: (0, 0),
+            checkpoints: BTreeSet::new(),
+        });
+
+        if line.function == "???" {
+            line.function = function;
+        }
+        line.addr_range = match (line.addr_range, loc.addr_range) {
+            ((0, 0), range) | (range, (0, 0)) => range,
+            ((start, end), (other_start, other_end)) => {
+                (start.min(other_start), end.max(other_end))
+            }
+        };
+        line.checkpoints.extend(loc.checkpoints);
+    }
+
+    /// The profile of the lines, made of the same items as a parsed memviz profile
+    pub fn build(self) -> Profile {
+        let mut items = BTreeSet::new();
+        let mut files: BTreeMap<String, Rc<RefCell<FileInfo>>> = BTreeMap::new();
+        let mut functions: BTreeMap<String, Rc<String>> = BTreeMap::new();
+
+        for ((file, nb, _), line) in self.lines {
+            let f = files
+                .entry(file)
+                .or_insert_with_key(|file| {
+                    let path = PathInfo::new(String::new(), file.clone());
+                    let f = Rc::new(RefCell::new(FileInfo::new(path)));
+
+                    items.insert(ProfileItem::File(f.clone()));
+                    f
+                })
+                .clone();
+            let function = functions
+                .entry(line.function)
+                .or_insert_with_key(|function| Rc::new(function.clone()))
+                .clone();
+
+            let has_debug_info = f.borrow().has_debug_info;
+            let l = LineInfo::new(
+                nb,
+                line.addr_range,
+                None,
+                Some(function),
+                has_debug_info,
+                line.checkpoints,
+            );
+
+            items.insert(ProfileItem::Line(f, l));
+        }
+
+        Profile::new(items, self.checkpoints)
+    }
+}
+
+/// Name of the checkpoint of a whole input file: its name without extension
+fn file_checkpoint_name(path: &std::path::Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProfileBuilder;
+    use crate::model::profile::{CodeLoc, Profile, ProfileItem};
+
+    fn loc(file: &str, function: &str, nb: usize, checkpoints: &[u32]) -> CodeLoc {
+        CodeLoc {
+            file: String::from(file),
+            function: String::from(function),
+            nb,
+            addr_range: (nb as u64 * 0x10, nb as u64 * 0x10 + 4),
+            checkpoints: checkpoints.iter().cloned().collect(),
+        }
+    }
+
+    #[test]
+    fn build_profile() {
+        let mut builder = ProfileBuilder::new();
+        assert_eq!(builder.checkpoint("a"), 0);
+        assert_eq!(builder.checkpoint("b"), 1);
+        assert_eq!(builder.checkpoint("a"), 0);
+
+        builder.add(loc("main.c", "main", 5, &[0]));
+        builder.add(loc("main.c", "main", 3, &[]));
+        builder.add(loc("main.c", "main", 5, &[1]));
+        builder.add(loc("???", "f", 0, &[1]));
+        builder.add(CodeLoc {
+            addr_range: (0x44, 0x48),
+            ..loc("main.c", "main", 3, &[])
+        });
+        // Another instance of a template, the first name is kept
+        builder.add(loc("main.c", "g<int>", 7, &[0]));
+        builder.add(loc("main.c", "g<long>", 7, &[1]));
+        // Without debug information, lines are told apart by function
+        builder.add(loc("???", "g", 0, &[0]));
+        let profile = builder.build();
+
+        assert_eq!(profile.checkpoint_name(1), Some("b"));
+
+        let items: Vec<String> = profile
+            .items
+            .iter()
+            .map(|item| match item {
+                ProfileItem::File(f) => f.borrow().path.file.clone(),
+                ProfileItem::Line(_, l) => {
+                    format!("{} {:x?} {:?}", l.nb, l.addr_range, l.checkpoints)
+                }
+            })
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                "???",
+                "0 (0, 4) {1}",
+                "0 (0, 4) {0}",
+                "main.c",
+                "3 (30, 48) {}",
+                "5 (50, 54) {0, 1}",
+                "7 (70, 74) {0, 1}"
+            ]
+        );
+
+        // The same items as the memviz parser
+        let parsed = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let mut builder = ProfileBuilder::new();
+        builder.checkpoint("memviz_begin");
+        builder.checkpoint("Before_hello");
+
+        for item in parsed.items.iter() {
+            if let ProfileItem::Line(f, l) = item {
+                builder.add(CodeLoc {
+                    file: f.borrow().path.file.clone(),
+                    function: l.function.as_ref().unwrap().to_string(),
+                    nb: l.nb,
+                    addr_range: l.addr_range,
+                    checkpoints: l.checkpoints.clone(),
+                });
+            }
+        }
+
+        let built = builder.build();
+        assert_eq!(built.checkpoints, parsed.checkpoints);
+        assert_eq!(built.items, parsed.items);
+    }
+}
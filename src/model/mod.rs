@@ -1 +1,12 @@
+//! Profiles and operations on them
+
+/// Comparison of two profiles
+pub mod diff;
+/// Writing profiles in the formats of other tools
+pub mod export;
+/// Making profiles of the coverage reports of other tools
+pub mod import;
+/// Reading and updating profiles
 pub mod profile;
+/// Summary of a profile
+pub mod stats;
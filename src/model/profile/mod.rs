@@ -1,23 +1,29 @@
+use serde::{Deserialize, Serialize};
 use std::{
     cell::RefCell,
     cmp::Ordering,
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     iter::{Cloned, Peekable},
     marker::PhantomData,
-    path::Path,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
 mod parser;
+pub mod schema;
+mod stream;
+
+pub use parser::ParseError;
+pub use stream::{CodeLoc, StreamDecoder, Update};
 
 fn cmp_option_helper<T: Ord>(a: &Option<T>, b: &Option<T>) -> Ordering {
     let comp = a.cmp(b);
 
     if comp != Ordering::Equal {
-        if let None = a {
+        if a.is_none() {
             return Ordering::Greater;
         }
-        if let None = b {
+        if b.is_none() {
             return Ordering::Less;
         }
     }
@@ -26,9 +32,11 @@ fn cmp_option_helper<T: Ord>(a: &Option<T>, b: &Option<T>) -> Ordering {
 }
 
 /// Path to a source code file
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct PathInfo {
+    /// Compilation directory, empty for unknown files
     pub directory: String,
+    /// File name, relative to the directory. `???` when the debug information is missing.
     pub file: String,
 }
 
@@ -47,7 +55,7 @@ impl Ord for PathInfo {
 
 impl PartialOrd for PathInfo {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
@@ -61,19 +69,24 @@ impl PathInfo {
             } else {
                 directory
             },
-            file: file,
+            file,
         }
     }
 
+    /// The path of the file: its name joined to its directory
     pub fn expand(&self) -> std::path::PathBuf {
         Path::new(&self.directory).join(&self.file)
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+/// A source file of the profile
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct FileInfo {
+    /// Where the file was when the program was compiled
     pub path: PathInfo,
+    /// False for the `???` file, which gathers the code without debug information
     pub has_debug_info: bool,
+    /// Checkpoints met by at least one line of the file
     pub checkpoints: BTreeSet<u32>,
 }
 
@@ -100,31 +113,67 @@ impl Ord for FileInfo {
 
 impl PartialOrd for FileInfo {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
 impl FileInfo {
+    /// A file without checkpoint
     pub fn new(path: PathInfo) -> FileInfo {
         FileInfo {
             has_debug_info: path.file != "???",
-            path: path,
+            path,
             checkpoints: BTreeSet::new(),
         }
     }
 
+    /// Read the source file from its recorded path
     pub fn get_file_content(&self) -> std::io::Result<String> {
         std::fs::read_to_string(self.path.expand())
     }
+
+    /// Read the source file from its recorded path, or else from the first search
+    /// directory containing its path (or only its name) relative to that directory
+    pub fn find_file_content(&self, search_paths: &[PathBuf]) -> std::io::Result<String> {
+        let first_error = match self.get_file_content() {
+            Ok(content) => return Ok(content),
+            Err(e) => e,
+        };
+
+        let path = self.path.expand();
+        let relative = path.strip_prefix("/").unwrap_or(&path);
+
+        for dir in search_paths {
+            let mut candidates = vec![dir.join(relative)];
+            candidates.extend(path.file_name().map(|name| dir.join(name)));
+
+            for candidate in candidates {
+                if let Ok(content) = std::fs::read_to_string(candidate) {
+                    return Ok(content);
+                }
+            }
+        }
+
+        Err(first_error)
+    }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+/// A line of a source file
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct LineInfo {
+    /// Line number, starting at 1
     pub nb: usize,
+    /// Addresses of the first and last instructions of the line, `(0, 0)` for lines
+    /// without code
     pub addr_range: (u64, u64),
+    /// Source code, once the profile is synced with the source files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub line_content: Option<String>,
+    /// Function containing the line, when known
     pub function: Option<Rc<String>>,
+    /// Ids of the checkpoints met by the line
     pub checkpoints: BTreeSet<u32>,
+    /// Whether the line number comes from debug information
     pub has_debug_info: bool,
 }
 
@@ -142,11 +191,12 @@ impl Ord for LineInfo {
 
 impl PartialOrd for LineInfo {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
 impl LineInfo {
+    /// A line. `is_file_available` tells whether the line has debug information.
     pub fn new(
         nb: usize,
         addr_range: (u64, u64),
@@ -156,35 +206,46 @@ impl LineInfo {
         checkpoints: BTreeSet<u32>,
     ) -> LineInfo {
         LineInfo {
-            nb: nb,
-            addr_range: addr_range,
-            line_content: line_content,
-            function: function,
-            checkpoints: checkpoints,
+            nb,
+            addr_range,
+            line_content,
+            function,
+            checkpoints,
             has_debug_info: is_file_available,
         }
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialOrd)]
+/// Identifies a line across two versions of a profile.
+/// Without debug information, line numbers are only meaningful within a function.
+pub type LineKey = (PathInfo, usize, Option<String>);
+
+/// An item of a profile: a file, followed by its lines.
+/// Lines share the `FileInfo` of their file.
+///
+/// Items are ordered by the `PathInfo` of their file, which is never modified through
+/// the `RefCell`: the checkpoints of a file can change while it is in a set. Hence
+/// `clippy.toml` does not count the `RefCell` as interior mutability for
+/// `mutable_key_type`.
+#[derive(Clone, Debug, Eq)]
 pub enum ProfileItem {
+    /// Header of a file
     File(Rc<RefCell<FileInfo>>),
+    /// Line of a file
     Line(Rc<RefCell<FileInfo>>, LineInfo),
 }
 
 impl PartialEq for ProfileItem {
     fn eq(&self, other: &ProfileItem) -> bool {
         match (self, other) {
-            (ProfileItem::File(f1), ProfileItem::File(f2)) => return f1 == f2,
-            (ProfileItem::Line(f1, l1), ProfileItem::Line(f2, l2)) => {
-                return (f1 == f2) && (l1 == l2)
-            }
-            (_, _) => return false,
+            (ProfileItem::File(f1), ProfileItem::File(f2)) => f1 == f2,
+            (ProfileItem::Line(f1, l1), ProfileItem::Line(f2, l2)) => (f1 == f2) && (l1 == l2),
+            (_, _) => false,
         }
     }
 }
 
-impl<'a> Ord for ProfileItem {
+impl Ord for ProfileItem {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
             (ProfileItem::File(f1), ProfileItem::File(f2)) => f1.cmp(f2),
@@ -215,14 +276,34 @@ impl<'a> Ord for ProfileItem {
     }
 }
 
+impl PartialOrd for ProfileItem {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl<'a> ProfileItem {
+    /// Whether the item is the header of a file
     pub fn is_file(&'a self) -> bool {
+        matches!(self, ProfileItem::File(_))
+    }
+
+    /// The key of a line, `None` for files
+    pub fn line_key(&self) -> Option<LineKey> {
         match self {
-            ProfileItem::File(_) => true,
-            _ => false,
+            ProfileItem::File(_) => None,
+            ProfileItem::Line(f, l) => Some((
+                f.borrow().path.clone(),
+                l.nb,
+                match l.has_debug_info {
+                    true => None,
+                    false => l.function.as_ref().map(|f| f.to_string()),
+                },
+            )),
         }
     }
 
+    /// Whether both items belong to the same file
     pub fn is_in_same_file<'b: 'a>(&'a self, other: &'b ProfileItem) -> bool {
         match (self, other) {
             (ProfileItem::Line(f1, _), ProfileItem::File(f2)) => f1 == f2,
@@ -232,6 +313,7 @@ impl<'a> ProfileItem {
         }
     }
 
+    /// The file of the item, or the file the line belongs to
     pub fn get_file_info(&'a self) -> Rc<RefCell<FileInfo>> {
         match self {
             ProfileItem::File(f) => f.clone(),
@@ -245,13 +327,14 @@ impl<'a> ProfileItem {
 /// The first item is always a ProfileItem::File.
 /// It is followed by all the available lines ordered by line number.
 /// Right after parsing, lines are not synced with the corresponding source file.  You will
-/// have to call `sync_with_fs` to do that.
+/// have to call `synced` to do that.
 pub struct FileSection<'a> {
     iter: Box<dyn Iterator<Item = &'a ProfileItem> + 'a>,
     phantom: PhantomData<&'a Profile>,
 }
 
 impl<'a> FileSection<'a> {
+    /// The section of the file `f` of the profile
     pub fn new(profile: &'a Profile, f: &'a ProfileItem) -> FileSection<'a> {
         let file = std::iter::once(f);
         let lines = profile
@@ -265,8 +348,9 @@ impl<'a> FileSection<'a> {
         }
     }
 
-    pub fn synced(self) -> SyncedFileSection<'a> {
-        SyncedFileSection::new(self)
+    /// The section with every line of the source file, see `Profile::synced_in`
+    pub fn synced(self, search_paths: &[PathBuf]) -> SyncedFileSection<'a> {
+        SyncedFileSection::new(self, search_paths)
     }
 }
 
@@ -278,6 +362,9 @@ impl<'a> Iterator for FileSection<'a> {
     }
 }
 
+/// An iterator over a file section, merged with the lines of the source file.
+/// Lines of the source file that are not in the profile are added, with their content.
+/// If the source file cannot be read, this is a copy of the section.
 pub struct SyncedFileSection<'a> {
     file: Rc<RefCell<FileInfo>>,
     section: Peekable<Cloned<FileSection<'a>>>,
@@ -286,12 +373,13 @@ pub struct SyncedFileSection<'a> {
 }
 
 impl<'a> SyncedFileSection<'a> {
-    pub fn new(section: FileSection<'a>) -> SyncedFileSection<'a> {
+    /// Read the source file of the section, see `FileInfo::find_file_content`
+    pub fn new(section: FileSection<'a>, search_paths: &[PathBuf]) -> SyncedFileSection<'a> {
         let mut new_iter = section.cloned().peekable();
 
         let file_info = new_iter.peek().unwrap().get_file_info();
 
-        let maybe_content = file_info.borrow().get_file_content();
+        let maybe_content = file_info.borrow().find_file_content(search_paths);
 
         match maybe_content {
             Ok(content) => SyncedFileSection {
@@ -316,7 +404,7 @@ impl<'a> Iterator for SyncedFileSection<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         // If lines are unavailable this is a copy of the section
-        if let None = self.lines {
+        if self.lines.is_none() {
             return self.section.next();
         }
 
@@ -367,6 +455,7 @@ pub struct FileSections<'a> {
 }
 
 impl<'a> FileSections<'a> {
+    /// The sections of the files of the profile
     pub fn new(profile: &'a Profile) -> FileSections<'a> {
         let files = profile.items.iter().filter(|item| item.is_file());
         let iter = files.map(move |file| profile.file_section(file));
@@ -385,18 +474,19 @@ impl<'a> Iterator for FileSections<'a> {
     }
 }
 
+/// An iterator over the items of all the synced file sections
 pub struct SyncedProfileItems<'a> {
     iter: Box<dyn Iterator<Item = ProfileItem> + 'a>,
 }
 
 impl<'a> SyncedProfileItems<'a> {
-    pub fn new(profile: &'a Profile) -> SyncedProfileItems<'a> {
+    /// Sync all the file sections of the profile
+    pub fn new(profile: &'a Profile, search_paths: &'a [PathBuf]) -> SyncedProfileItems<'a> {
         SyncedProfileItems {
             iter: Box::new(
                 profile
                     .file_sections()
-                    .map(move |section| section.synced())
-                    .flatten(),
+                    .flat_map(move |section| section.synced(search_paths)),
             ),
         }
     }
@@ -410,53 +500,86 @@ impl<'a> Iterator for SyncedProfileItems<'a> {
     }
 }
 
+/// A checkpoint profile: the lines of code instrumented by memviz, and the checkpoints
+/// they met
 pub struct Profile {
+    /// Files and lines, each file being followed by its lines
     pub items: BTreeSet<ProfileItem>,
+    /// Checkpoint names, indexed by checkpoint id
+    pub checkpoints: BTreeMap<u32, String>,
 }
 
 impl<'a> Profile {
-    pub fn new(items: BTreeSet<ProfileItem>) -> Profile{
-        let mut ret = Profile {
-            items: items
-        };
+    /// A profile made of these items and checkpoints. The checkpoints of the files are
+    /// computed from the ones of their lines.
+    pub fn new(items: BTreeSet<ProfileItem>, checkpoints: BTreeMap<u32, String>) -> Profile {
+        let mut ret = Profile { items, checkpoints };
 
         ret.set_files_checkpoints();
-        
-        ret 
+
+        ret
     }
 
     fn set_files_checkpoints(&'a mut self) {
         for section in self.file_sections() {
             for item in section {
-                match item {
-                    ProfileItem::Line(f, l) => {
-                        for c in &l.checkpoints {
-                            f.borrow_mut().checkpoints.insert(*c);
-                        }
-                    },
-                    _ => {}
+                if let ProfileItem::Line(f, l) = item {
+                    for c in &l.checkpoints {
+                        f.borrow_mut().checkpoints.insert(*c);
+                    }
                 }
             }
         }
     }
 
+    /// The name of a checkpoint, given its id
+    pub fn checkpoint_name(&self, id: u32) -> Option<&str> {
+        self.checkpoints.get(&id).map(|name| name.as_str())
+    }
+
+    /// The id of a declared checkpoint, given its name or its id.
+    /// Names are looked up first, as they may be numbers.
+    pub fn checkpoint_id(&self, s: &str) -> Result<u32, String> {
+        let by_name = self
+            .checkpoints
+            .iter()
+            .find(|(_, name)| name.as_str() == s)
+            .map(|(id, _)| *id);
+
+        by_name
+            .or_else(|| {
+                s.parse()
+                    .ok()
+                    .filter(|id| self.checkpoints.contains_key(id))
+            })
+            .ok_or_else(|| format!("unknown checkpoint {:?}", s))
+    }
+
+    /// The items, grouped by file
     pub fn file_sections(&'a self) -> FileSections<'a> {
         FileSections::new(self)
     }
 
+    /// The items of the file `item`
     pub fn file_section(&'a self, item: &'a ProfileItem) -> FileSection<'a> {
         FileSection::new(self, item)
     }
 
-    pub fn synced_items(&'a self) -> SyncedProfileItems<'a> {
-        SyncedProfileItems::new(self)
+    /// The items, with every line of the source files
+    pub fn synced_items(&'a self, search_paths: &'a [PathBuf]) -> SyncedProfileItems<'a> {
+        SyncedProfileItems::new(self, search_paths)
     }
 
+    /// Sync with the source files, see `Profile::synced_in`
     pub fn synced(&self) -> Profile {
+        self.synced_in(&[])
+    }
+
+    /// Sync with the source files, looking for missing ones in the search directories
+    pub fn synced_in(&self, search_paths: &[PathBuf]) -> Profile {
         Profile {
-            items: self
-                .synced_items()
-                .collect(),
+            items: self.synced_items(search_paths).collect(),
+            checkpoints: self.checkpoints.clone(),
         }
     }
 }
@@ -464,7 +587,11 @@ impl<'a> Profile {
 #[cfg(test)]
 mod tests {
     use crate::model::profile::{FileInfo, LineInfo, PathInfo, Profile, ProfileItem};
-    use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
+    use std::{
+        cell::RefCell,
+        collections::{BTreeMap, BTreeSet},
+        rc::Rc,
+    };
 
     #[test]
     fn line_cmp() {
@@ -641,7 +768,10 @@ mod tests {
         items.insert(i4.clone());
         items.insert(i5.clone());
 
-        let profile = Profile { items };
+        let profile = Profile {
+            items,
+            checkpoints: BTreeMap::new(),
+        };
 
         let mut file_sections = profile.file_sections();
 
@@ -733,7 +863,7 @@ mod tests {
             LineInfo::new(
                 1,
                 (0, 0),
-                Some(String::from(file_lines[0].clone())),
+                Some(String::from(file_lines[0])),
                 None,
                 true,
                 bt_set!(),
@@ -744,7 +874,7 @@ mod tests {
             LineInfo::new(
                 2,
                 (0, 0),
-                Some(String::from(file_lines[1].clone())),
+                Some(String::from(file_lines[1])),
                 None,
                 true,
                 bt_set!(),
@@ -755,7 +885,7 @@ mod tests {
             LineInfo::new(
                 3,
                 (0, 0),
-                Some(String::from(file_lines[2].clone())),
+                Some(String::from(file_lines[2])),
                 None,
                 true,
                 bt_set!(),
@@ -766,7 +896,7 @@ mod tests {
             LineInfo::new(
                 4,
                 (0, 0),
-                Some(String::from(file_lines[3].clone())),
+                Some(String::from(file_lines[3])),
                 None,
                 true,
                 bt_set!(),
@@ -777,7 +907,7 @@ mod tests {
             LineInfo::new(
                 5,
                 (0, 0),
-                Some(String::from(file_lines[4].clone())),
+                Some(String::from(file_lines[4])),
                 None,
                 true,
                 bt_set!(),
@@ -788,7 +918,7 @@ mod tests {
             LineInfo::new(
                 6,
                 (0, 0),
-                Some(String::from(file_lines[5].clone())),
+                Some(String::from(file_lines[5])),
                 None,
                 true,
                 bt_set!(),
@@ -799,7 +929,7 @@ mod tests {
             LineInfo::new(
                 7,
                 (0, 0),
-                Some(String::from(file_lines[6].clone())),
+                Some(String::from(file_lines[6])),
                 None,
                 true,
                 bt_set!(),
@@ -810,7 +940,7 @@ mod tests {
             LineInfo::new(
                 8,
                 (0, 0),
-                Some(String::from(file_lines[7].clone())),
+                Some(String::from(file_lines[7])),
                 None,
                 true,
                 bt_set!(),
@@ -821,7 +951,7 @@ mod tests {
             LineInfo::new(
                 9,
                 (0x1089ac, 0x1089c4),
-                Some(String::from(file_lines[8].clone())),
+                Some(String::from(file_lines[8])),
                 Some(func.clone()),
                 true,
                 bt_set!(0),
@@ -832,7 +962,7 @@ mod tests {
             LineInfo::new(
                 10,
                 (0, 0),
-                Some(String::from(file_lines[9].clone())),
+                Some(String::from(file_lines[9])),
                 None,
                 true,
                 bt_set!(),
@@ -843,7 +973,7 @@ mod tests {
             LineInfo::new(
                 11,
                 (0x1089c6, 0x1089cb),
-                Some(String::from(file_lines[10].clone())),
+                Some(String::from(file_lines[10])),
                 Some(func.clone()),
                 true,
                 bt_set!(0),
@@ -854,7 +984,7 @@ mod tests {
             LineInfo::new(
                 12,
                 (0, 0),
-                Some(String::from(file_lines[11].clone())),
+                Some(String::from(file_lines[11])),
                 None,
                 true,
                 bt_set!(),
@@ -865,7 +995,7 @@ mod tests {
             LineInfo::new(
                 13,
                 (0x1089d1, 0x108a29),
-                Some(String::from(file_lines[12].clone())),
+                Some(String::from(file_lines[12])),
                 Some(func.clone()),
                 true,
                 bt_set!(0, 1),
@@ -876,7 +1006,7 @@ mod tests {
             LineInfo::new(
                 14,
                 (0, 0),
-                Some(String::from(file_lines[13].clone())),
+                Some(String::from(file_lines[13])),
                 None,
                 true,
                 bt_set!(),
@@ -887,7 +1017,7 @@ mod tests {
             LineInfo::new(
                 15,
                 (0x108a2d, 0x108a34),
-                Some(String::from(file_lines[14].clone())),
+                Some(String::from(file_lines[14])),
                 Some(func.clone()),
                 true,
                 bt_set!(1),
@@ -898,7 +1028,7 @@ mod tests {
             LineInfo::new(
                 16,
                 (0, 0),
-                Some(String::from(file_lines[15].clone())),
+                Some(String::from(file_lines[15])),
                 None,
                 true,
                 bt_set!(),
@@ -909,7 +1039,7 @@ mod tests {
             LineInfo::new(
                 17,
                 (0, 0),
-                Some(String::from(file_lines[16].clone())),
+                Some(String::from(file_lines[16])),
                 None,
                 true,
                 bt_set!(),
@@ -920,7 +1050,7 @@ mod tests {
             LineInfo::new(
                 18,
                 (0, 0),
-                Some(String::from(file_lines[17].clone())),
+                Some(String::from(file_lines[17])),
                 None,
                 true,
                 bt_set!(),
@@ -931,7 +1061,7 @@ mod tests {
             LineInfo::new(
                 19,
                 (0x108a4e, 0x108a55),
-                Some(String::from(file_lines[18].clone())),
+                Some(String::from(file_lines[18])),
                 Some(func.clone()),
                 true,
                 bt_set!(1),
@@ -942,7 +1072,7 @@ mod tests {
             LineInfo::new(
                 20,
                 (0, 0),
-                Some(String::from(file_lines[19].clone())),
+                Some(String::from(file_lines[19])),
                 None,
                 true,
                 bt_set!(),
@@ -953,7 +1083,7 @@ mod tests {
             LineInfo::new(
                 21,
                 (0, 0),
-                Some(String::from(file_lines[20].clone())),
+                Some(String::from(file_lines[20])),
                 None,
                 true,
                 bt_set!(),
@@ -964,7 +1094,7 @@ mod tests {
             LineInfo::new(
                 22,
                 (0, 0),
-                Some(String::from(file_lines[21].clone())),
+                Some(String::from(file_lines[21])),
                 None,
                 true,
                 bt_set!(),
@@ -975,7 +1105,7 @@ mod tests {
             LineInfo::new(
                 23,
                 (0, 0),
-                Some(String::from(file_lines[22].clone())),
+                Some(String::from(file_lines[22])),
                 None,
                 true,
                 bt_set!(),
@@ -986,7 +1116,7 @@ mod tests {
             LineInfo::new(
                 24,
                 (0, 0),
-                Some(String::from(file_lines[23].clone())),
+                Some(String::from(file_lines[23])),
                 None,
                 true,
                 bt_set!(),
@@ -997,7 +1127,7 @@ mod tests {
             LineInfo::new(
                 25,
                 (0, 0),
-                Some(String::from(file_lines[24].clone())),
+                Some(String::from(file_lines[24])),
                 None,
                 true,
                 bt_set!(),
@@ -1008,12 +1138,29 @@ mod tests {
             //checkpoints: vec!(),
             //file_sections: vec!(items1)
             items: items1,
+            checkpoints: BTreeMap::new(),
         };
 
-        let p2 = Profile { items: items2 };
+        let p2 = Profile {
+            items: items2,
+            checkpoints: BTreeMap::new(),
+        };
 
         for (i1, i2) in p2.items.iter().zip(p1.synced().items.iter()) {
             assert_eq!(i1, i2);
         }
     }
+
+    #[test]
+    fn checkpoint_ids() {
+        let mut profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        assert_eq!(profile.checkpoint_id("Before_hello"), Ok(1));
+        assert_eq!(profile.checkpoint_id("0"), Ok(0));
+        assert!(profile.checkpoint_id("99").is_err());
+        assert!(profile.checkpoint_id("after").is_err());
+
+        // Numeric names win over ids
+        profile.checkpoints.insert(5, String::from("0"));
+        assert_eq!(profile.checkpoint_id("0"), Ok(5));
+    }
 }
@@ -2,25 +2,73 @@ use crate::model::profile::{FileInfo, LineInfo, PathInfo, Profile, ProfileItem};
 use pest::Parser;
 
 use std::cell::RefCell;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt;
 use std::fs::read_to_string;
 use std::path::Path;
 use std::rc::Rc;
-use std::u64;
 
 #[derive(Parser)]
 #[grammar = "model/profile/grammar.pest"]
-struct ProfileParser;
+pub(super) struct ProfileParser;
+
+/// Why a profile cannot be read
+#[derive(Debug)]
+pub enum ParseError {
+    /// The file cannot be read
+    Io(std::io::Error),
+    /// The file is not a valid profile, the message shows where
+    Syntax(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "{}", e),
+            ParseError::Syntax(e) => write!(f, "parse error\n{}", e),
+        }
+    }
+}
+
+/// Line number, address range and checkpoints of a code location line
+type CodeLocFields = (usize, (u64, u64), BTreeSet<u32>);
+
+/// Parse a number the grammar accepted, which may still be out of range
+fn parse_number<T: TryFrom<u64>>(
+    field: &pest::iterators::Pair<Rule>,
+    s: &str,
+    radix: u32,
+) -> Result<T, String> {
+    let (line, column) = field.as_span().start_pos().line_col();
+
+    u64::from_str_radix(s, radix)
+        .ok()
+        .and_then(|n| T::try_from(n).ok())
+        .ok_or_else(|| {
+            format!(
+                "line {}, column {}: number {:?} out of range",
+                line, column, s
+            )
+        })
+}
 
 impl Profile {
-    fn parse_checkpoint_name(p: pest::iterators::Pair<Rule>) -> Option<String> {
+    pub(super) fn parse_checkpoint_id(p: pest::iterators::Pair<Rule>) -> Result<u32, String> {
+        for field in p.clone().into_inner() {
+            if field.as_rule() == Rule::dec {
+                return parse_number(&field, field.as_str().trim(), 10);
+            }
+        }
+
+        Err(format!("missing checkpoint id in {:?}", p.as_str()))
+    }
+
+    pub(super) fn parse_checkpoint_name(p: pest::iterators::Pair<Rule>) -> Option<String> {
         for field in p.into_inner() {
-            match field.as_rule() {
-                Rule::checkpoint_name => {
-                    let s = String::from(field.as_str());
-                    return Some(s);
-                }
-                _ => {}
+            if field.as_rule() == Rule::checkpoint_name {
+                let s = String::from(field.as_str());
+                return Some(s);
             }
         }
 
@@ -29,80 +77,87 @@ impl Profile {
 
     fn parse_checkpoint_id_section(
         section: pest::iterators::Pair<Rule>,
-        checkpoints: &mut Vec<String>,
-    ) {
+        checkpoints: &mut BTreeMap<u32, String>,
+    ) -> Result<(), String> {
         for line in section.into_inner() {
-            match line.as_rule() {
-                Rule::checkpoint_line => {
-                    checkpoints.push(Profile::parse_checkpoint_name(line).unwrap());
-                }
-                _ => {}
+            if line.as_rule() == Rule::checkpoint_line {
+                let id = Profile::parse_checkpoint_id(line.clone())?;
+                checkpoints.insert(id, Profile::parse_checkpoint_name(line).unwrap());
             }
         }
+
+        Ok(())
     }
 
-    fn parse_code_loc_line(
+    /// Line number, address range and checkpoints of a code location line
+    pub(super) fn parse_code_loc_fields(
         line: pest::iterators::Pair<Rule>,
-        file_section: Rc<RefCell<FileInfo>>,
-        function_name: &Rc<String>,
-        items: &RefCell<BTreeSet<ProfileItem>>,
-    ) {
+    ) -> Result<CodeLocFields, String> {
         let mut min_addr: u64 = 0;
         let mut max_addr: u64 = 0;
         let mut line_nb: usize = 0;
-        let mut checkpoints: Option<BTreeSet<u32>> = None;
+        let mut checkpoints: BTreeSet<u32> = BTreeSet::new();
 
         for field in line.into_inner() {
             match field.as_rule() {
                 Rule::line_nb => {
-                    line_nb = field.into_inner().as_str().trim().parse().unwrap();
+                    line_nb = parse_number(&field, field.clone().into_inner().as_str().trim(), 10)?;
                 }
 
                 Rule::addr_range => {
-                    let pair: Vec<&str> = field.into_inner().map(|x| x.as_str()).collect();
+                    let pair: Vec<&str> = field.clone().into_inner().map(|x| x.as_str()).collect();
 
                     let mut without_prefix = pair[0].trim_start_matches("0x");
-                    min_addr = u64::from_str_radix(without_prefix, 16).unwrap();
+                    min_addr = parse_number(&field, without_prefix, 16)?;
 
                     without_prefix = pair[1].trim_start_matches("0x");
-                    max_addr = u64::from_str_radix(without_prefix, 16).unwrap();
+                    max_addr = parse_number(&field, without_prefix, 16)?;
                 }
 
                 Rule::checkpoint_list => {
-                    checkpoints = Some(
-                        field
-                            .into_inner()
-                            .as_str()
-                            .split(" ")
-                            .into_iter()
-                            .map(|token| token.trim().parse::<u32>().unwrap())
-                            .collect(),
-                    );
+                    checkpoints = field
+                        .as_str()
+                        .split_whitespace()
+                        .map(|token| parse_number(&field, token, 10))
+                        .collect::<Result<_, _>>()?;
                 }
 
                 _ => {}
             }
         }
 
+        Ok((line_nb, (min_addr, max_addr), checkpoints))
+    }
+
+    fn parse_code_loc_line(
+        line: pest::iterators::Pair<Rule>,
+        file_section: Rc<RefCell<FileInfo>>,
+        function_name: &Rc<String>,
+        items: &RefCell<BTreeSet<ProfileItem>>,
+    ) -> Result<(), String> {
+        let (line_nb, addr_range, checkpoints) = Profile::parse_code_loc_fields(line)?;
+
         let l = LineInfo::new(
             line_nb,
-            (min_addr, max_addr),
+            addr_range,
             None,
             Some(function_name.clone()),
             file_section.borrow().has_debug_info,
-            checkpoints.unwrap(),
+            checkpoints,
         );
 
         items
             .borrow_mut()
             .insert(ProfileItem::Line(file_section, l));
+
+        Ok(())
     }
 
     fn parse_function_section(
         section: pest::iterators::Pair<Rule>,
         file_section: Rc<RefCell<FileInfo>>,
         items: &RefCell<BTreeSet<ProfileItem>>,
-    ) {
+    ) -> Result<(), String> {
         let mut function_name = Rc::new(String::from("???"));
 
         for line in section.into_inner() {
@@ -112,18 +167,25 @@ impl Profile {
                 }
 
                 Rule::code_loc_line => {
-                    Profile::parse_code_loc_line(line, file_section.clone(), &function_name, items);
+                    Profile::parse_code_loc_line(
+                        line,
+                        file_section.clone(),
+                        &function_name,
+                        items,
+                    )?;
                 }
 
                 _ => {}
             }
         }
+
+        Ok(())
     }
 
     fn parse_file_section(
         section: pest::iterators::Pair<Rule>,
         items: &RefCell<BTreeSet<ProfileItem>>,
-    ) {
+    ) -> Result<(), String> {
         let mut pairs = section.into_inner();
 
         let line = pairs.next().unwrap();
@@ -148,35 +210,54 @@ impl Profile {
         for line in pairs {
             match line.as_rule() {
                 Rule::function_section => {
-                    Profile::parse_function_section(line, fl.as_ref().unwrap().clone(), items);
+                    Profile::parse_function_section(line, fl.as_ref().unwrap().clone(), items)?;
                 }
                 _ => unreachable!(),
             }
         }
+
+        Ok(())
     }
 
     fn parse_code_locs_section(
         section: pest::iterators::Pair<Rule>,
         items: &RefCell<BTreeSet<ProfileItem>>,
-    ) {
+    ) -> Result<(), String> {
         for line in section.into_inner() {
             match line.as_rule() {
                 Rule::file_section => {
-                    Profile::parse_file_section(line, items);
+                    Profile::parse_file_section(line, items)?;
                 }
                 _ => unreachable!(),
             }
         }
+
+        Ok(())
     }
 
+    /// Read a profile file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file cannot be read or is not a valid profile, see `try_parse`.
     pub fn parse<P: AsRef<Path>>(path: P) -> Self {
-        let mut checkpoints = Vec::<String>::new();
+        Profile::try_parse(path).expect("Parse error")
+    }
+
+    /// Read a profile file, or a profile serialized as JSON (see `schema`)
+    pub fn try_parse<P: AsRef<Path>>(path: P) -> Result<Self, ParseError> {
+        let mut checkpoints = BTreeMap::new();
         let items = RefCell::new(BTreeSet::new());
 
-        let unparsed_file = read_to_string(path).unwrap();
+        let unparsed_file = read_to_string(path).map_err(ParseError::Io)?;
+
+        if unparsed_file.trim_start().starts_with('{') {
+            return serde_json::from_str(&unparsed_file)
+                .map_err(|e| ParseError::Syntax(e.to_string()));
+        }
 
         let ast = ProfileParser::parse(Rule::file, &unparsed_file)
-            .expect("Parse error")
+            .map_err(|e| ParseError::Syntax(e.to_string()))?
             .next()
             .unwrap();
 
@@ -188,20 +269,21 @@ impl Profile {
 
                 Rule::codelocs_section => Profile::parse_code_locs_section(section, &items),
 
-                _ => {}
+                _ => Ok(()),
             }
+            .map_err(ParseError::Syntax)?;
         }
 
-        Profile::new(items.into_inner())
+        Ok(Profile::new(items.into_inner(), checkpoints))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::model::profile::{FileInfo, LineInfo, PathInfo, Profile, ProfileItem};
+    use crate::model::profile::{FileInfo, LineInfo, ParseError, PathInfo, Profile, ProfileItem};
 
     use std::cell::RefCell;
-    use std::collections::BTreeSet;
+    use std::collections::{BTreeMap, BTreeSet};
     use std::rc::Rc;
 
     pub fn asset_memviz_checkpoint_28516() -> Profile {
@@ -273,13 +355,50 @@ mod tests {
             ),
         ));
 
-        Profile { items: items }
+        let mut checkpoints = BTreeMap::new();
+        checkpoints.insert(0, String::from("memviz_begin"));
+        checkpoints.insert(1, String::from("Before_hello"));
+
+        Profile::new(items, checkpoints)
     }
 
     #[test]
     fn parse_memviz_checkpoint_28516() {
         let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
 
-        assert_eq!(profile.items, asset_memviz_checkpoint_28516().items);
+        let expected = asset_memviz_checkpoint_28516();
+
+        assert_eq!(profile.items, expected.items);
+        assert_eq!(profile.checkpoints, expected.checkpoints);
+    }
+
+    #[test]
+    fn numbers_out_of_range() {
+        let path = std::env::temp_dir().join(format!("phaseviz-parser-{}", std::process::id()));
+        let profile = |checkpoint: &str, code_loc: &str| {
+            let text = format!(
+                "[checkpoint id]\n{} -> begin\n\n[met checkpoint]\nfl=a.c\nfn=main\n{}\n",
+                checkpoint, code_loc
+            );
+            std::fs::write(&path, text).unwrap();
+            Profile::try_parse(&path)
+        };
+
+        assert!(profile("0", "9 [0x10;0x20] -> 0").is_ok());
+
+        let overflows = [
+            profile("4294967296", "9 [0x10;0x20] -> 0"),
+            profile("0", "99999999999999999999 [0x10;0x20] -> 0"),
+            profile("0", "9 [0x10000000000000000;0x20] -> 0"),
+            profile("0", "9 [0x10;0x20] -> 0 4294967296"),
+        ];
+        std::fs::remove_file(&path).unwrap();
+
+        for result in overflows.iter() {
+            match result {
+                Err(ParseError::Syntax(e)) => assert!(e.contains("out of range"), "{}", e),
+                _ => panic!("expected a syntax error"),
+            }
+        }
     }
 }
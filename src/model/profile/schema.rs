@@ -0,0 +1,224 @@
+//! Serialization of profiles.
+//!
+//! Lines share the `FileInfo` of their file, so a serialized profile is made of a file
+//! table and of line records referring to the files by index:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "checkpoints": [{ "id": 0, "name": "memviz_begin" }, { "id": 1, "name": "Before_hello" }],
+//!   "files": [
+//!     {
+//!       "path": { "directory": "", "file": "assets/test/hello/hello.c" },
+//!       "has_debug_info": true,
+//!       "checkpoints": [0, 1]
+//!     }
+//!   ],
+//!   "lines": [
+//!     {
+//!       "file": 0,
+//!       "nb": 13,
+//!       "addr_range": [1084881, 1084969],
+//!       "function": "main",
+//!       "checkpoints": [0, 1],
+//!       "has_debug_info": true
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - `version` is `SCHEMA_VERSION`, increased on incompatible changes
+//! - `addr_range` holds the addresses of the first and last instructions of the line,
+//!   `[0, 0]` for lines without code. Addresses above 2^53 are not exact in JavaScript.
+//! - `function` is `null` when unknown
+//! - `line_content` is only present in profiles synced with their source files
+//! - files and lines are in the order of the profile: by path, then by line number
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    rc::Rc,
+};
+
+use super::{FileInfo, LineInfo, Profile, ProfileItem};
+
+/// Version of the serialized profile format
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Checkpoint {
+    id: u32,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LineRecord {
+    /// Index in the file table
+    file: usize,
+    #[serde(flatten)]
+    line: LineInfo,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProfileData {
+    version: u32,
+    checkpoints: Vec<Checkpoint>,
+    files: Vec<FileInfo>,
+    lines: Vec<LineRecord>,
+}
+
+impl From<&Profile> for ProfileData {
+    fn from(profile: &Profile) -> Self {
+        let mut files = vec![];
+        let mut lines = vec![];
+        let mut indexes: Vec<Rc<RefCell<FileInfo>>> = vec![];
+
+        for item in profile.items.iter() {
+            match item {
+                ProfileItem::File(f) => {
+                    indexes.push(f.clone());
+                    files.push(f.borrow().clone());
+                }
+                ProfileItem::Line(f, l) => {
+                    let file = match indexes.iter().rposition(|i| Rc::ptr_eq(i, f)) {
+                        Some(index) => index,
+                        None => {
+                            indexes.push(f.clone());
+                            files.push(f.borrow().clone());
+                            files.len() - 1
+                        }
+                    };
+
+                    lines.push(LineRecord {
+                        file,
+                        line: l.clone(),
+                    });
+                }
+            }
+        }
+
+        ProfileData {
+            version: SCHEMA_VERSION,
+            checkpoints: profile
+                .checkpoints
+                .iter()
+                .map(|(id, name)| Checkpoint {
+                    id: *id,
+                    name: name.clone(),
+                })
+                .collect(),
+            files,
+            lines,
+        }
+    }
+}
+
+impl TryFrom<ProfileData> for Profile {
+    type Error = String;
+
+    fn try_from(data: ProfileData) -> Result<Self, Self::Error> {
+        if data.version != SCHEMA_VERSION {
+            return Err(format!(
+                "unsupported profile version {}, expected {}",
+                data.version, SCHEMA_VERSION
+            ));
+        }
+
+        let files: Vec<Rc<RefCell<FileInfo>>> = data
+            .files
+            .into_iter()
+            .map(|f| Rc::new(RefCell::new(f)))
+            .collect();
+        let mut items: BTreeSet<ProfileItem> =
+            files.iter().cloned().map(ProfileItem::File).collect();
+
+        for record in data.lines {
+            let file = files.get(record.file).ok_or_else(|| {
+                format!(
+                    "line {} refers to file {}, out of {} files",
+                    record.line.nb,
+                    record.file,
+                    files.len()
+                )
+            })?;
+
+            items.insert(ProfileItem::Line(file.clone(), record.line));
+        }
+
+        let checkpoints: BTreeMap<u32, String> = data
+            .checkpoints
+            .into_iter()
+            .map(|c| (c.id, c.name))
+            .collect();
+
+        Ok(Profile::new(items, checkpoints))
+    }
+}
+
+impl Serialize for Profile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ProfileData::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Profile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = ProfileData::deserialize(deserializer)?;
+
+        Profile::try_from(data).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::profile::{Profile, ProfileItem};
+
+    #[test]
+    fn json_round_trip() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+
+        let json = serde_json::to_value(&profile).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["checkpoints"][1]["name"], "Before_hello");
+        assert_eq!(json["files"].as_array().unwrap().len(), 1);
+        assert_eq!(json["lines"][2]["file"], 0);
+        assert_eq!(json["lines"][2]["nb"], 13);
+        assert_eq!(json["lines"][2]["checkpoints"], serde_json::json!([0, 1]));
+
+        let read: Profile = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(read.checkpoints, profile.checkpoints);
+        assert_eq!(read.items, profile.items);
+
+        // Lines still share the file of their header
+        let file = read.items.iter().next().unwrap().get_file_info();
+        assert!(read.items.iter().all(|item| match item {
+            ProfileItem::Line(f, _) => std::rc::Rc::ptr_eq(f, &file),
+            ProfileItem::File(_) => true,
+        }));
+
+        let mut invalid = json.clone();
+        invalid["version"] = serde_json::json!(2);
+        assert!(serde_json::from_value::<Profile>(invalid).is_err());
+
+        let mut invalid = json;
+        invalid["lines"][0]["file"] = serde_json::json!(1);
+        assert!(serde_json::from_value::<Profile>(invalid).is_err());
+    }
+
+    #[test]
+    fn parse_json_file() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let path =
+            std::env::temp_dir().join(format!("phaseviz-schema-{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&profile).unwrap()).unwrap();
+
+        let read = Profile::try_parse(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+        assert_eq!(read.checkpoints, profile.checkpoints);
+        assert_eq!(read.items, profile.items);
+    }
+}
@@ -0,0 +1,298 @@
+//! Incremental profile updates.
+//!
+//! A running program can describe the profile one line at a time, using the same
+//! syntax as the profile file:
+//!
+//! ```text
+//! 2 -> after_init
+//! fl=src/main.c
+//! fn=main
+//! 13 [0x1089d1;0x108a29] -> 0 2
+//! ```
+//!
+//! - `ID -> NAME` declares (or renames) a checkpoint
+//! - `fl=FILE` and `fn=FUNCTION` select the file and function of the following records
+//! - `LINE [0xSTART;0xEND] -> ID...` records the checkpoints met on a line. The
+//!   checkpoints are added to the ones already known for this line.
+//!
+//! Section headers (`[checkpoint id]`, `[met checkpoint]`) and blank lines are ignored,
+//! so that a whole profile file can be replayed.
+
+use super::parser::{ProfileParser, Rule};
+use super::{FileInfo, LineInfo, PathInfo, Profile, ProfileItem};
+
+use pest::Parser;
+use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
+
+/// A code location record, with the file and function it belongs to
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CodeLoc {
+    /// Path of the file
+    pub file: String,
+    /// Function containing the line
+    pub function: String,
+    /// Line number
+    pub nb: usize,
+    /// Addresses of the first and last instructions of the line
+    pub addr_range: (u64, u64),
+    /// Ids of the checkpoints met by the line
+    pub checkpoints: BTreeSet<u32>,
+}
+
+/// A self-contained change to a profile
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Update {
+    /// Id and name of a checkpoint
+    Checkpoint(u32, String),
+    /// Checkpoints met by a line
+    CodeLoc(CodeLoc),
+}
+
+/// Turns the lines of a stream into updates.
+/// Each stream needs its own decoder, as records depend on the previous `fl=` and `fn=`
+/// lines.
+#[derive(Default)]
+pub struct StreamDecoder {
+    file: Option<String>,
+    function: Option<String>,
+}
+
+/// Parse `input` as a whole with `rule`
+fn parse_line(rule: Rule, input: &str) -> Option<pest::iterators::Pair<'_, Rule>> {
+    let pair = ProfileParser::parse(rule, input).ok()?.next()?;
+
+    if pair.as_span().end() == input.len() {
+        Some(pair)
+    } else {
+        None
+    }
+}
+
+impl StreamDecoder {
+    /// A decoder expecting a `fl=` line
+    pub fn new() -> StreamDecoder {
+        StreamDecoder::default()
+    }
+
+    /// Decode a line. Returns `Ok(None)` for lines that only change the decoder state.
+    pub fn decode(&mut self, line: &str) -> Result<Option<Update>, String> {
+        let line = line.trim();
+
+        if line.is_empty() || (line.starts_with('[') && line.ends_with(']')) {
+            return Ok(None);
+        }
+
+        let with_eol = format!("{}\n", line);
+
+        if let Some(pair) = parse_line(Rule::file_line, &with_eol) {
+            self.file = Some(String::from(pair.into_inner().as_str()));
+            self.function = None;
+            return Ok(None);
+        }
+
+        if let Some(pair) = parse_line(Rule::function_line, &with_eol) {
+            self.function = Some(String::from(pair.into_inner().as_str()));
+            return Ok(None);
+        }
+
+        if let Some(pair) = parse_line(Rule::checkpoint_line, line) {
+            let id = Profile::parse_checkpoint_id(pair.clone());
+            let name = Profile::parse_checkpoint_name(pair);
+
+            return match (id, name) {
+                (Ok(id), Some(name)) => Ok(Some(Update::Checkpoint(id, name))),
+                _ => Err(format!("invalid checkpoint declaration {:?}", line)),
+            };
+        }
+
+        if let Some(pair) = parse_line(Rule::code_loc_line, &with_eol) {
+            let file = match &self.file {
+                Some(file) => file.clone(),
+                None => return Err(String::from("code location before any fl= line")),
+            };
+
+            let (nb, addr_range, checkpoints) = Profile::parse_code_loc_fields(pair)?;
+
+            return Ok(Some(Update::CodeLoc(CodeLoc {
+                file,
+                function: self.function.clone().unwrap_or_else(|| String::from("???")),
+                nb,
+                addr_range,
+                checkpoints,
+            })));
+        }
+
+        Err(format!("unexpected line {:?}", line))
+    }
+}
+
+impl Profile {
+    fn file_item(&mut self, path: PathInfo) -> Rc<RefCell<FileInfo>> {
+        let existing = self.items.iter().find_map(|item| match item {
+            ProfileItem::File(f) if f.borrow().path == path => Some(f.clone()),
+            _ => None,
+        });
+
+        existing.unwrap_or_else(|| {
+            let f = Rc::new(RefCell::new(FileInfo::new(path)));
+            self.items.insert(ProfileItem::File(f.clone()));
+            f
+        })
+    }
+
+    /// Merge an update in the profile
+    pub fn apply(&mut self, update: Update) {
+        let loc = match update {
+            Update::Checkpoint(id, name) => {
+                self.checkpoints.insert(id, name);
+                return;
+            }
+            Update::CodeLoc(loc) => loc,
+        };
+
+        let CodeLoc {
+            file,
+            function,
+            nb,
+            addr_range,
+            mut checkpoints,
+        } = loc;
+
+        let f = self.file_item(PathInfo::new(String::new(), file));
+        let mut function = Rc::new(function);
+        let has_debug_info = f.borrow().has_debug_info;
+
+        // Like `LineInfo` ordering, lines with debug information are told apart by number
+        // only: an inlined line is the same line under another function
+        let existing = self
+            .items
+            .iter()
+            .find(|item| match item {
+                ProfileItem::Line(lf, l) => {
+                    Rc::ptr_eq(lf, &f)
+                        && l.nb == nb
+                        && (has_debug_info || l.function.as_ref() == Some(&function))
+                }
+                _ => false,
+            })
+            .cloned();
+
+        if let Some(item) = existing {
+            if let ProfileItem::Line(_, l) = &item {
+                checkpoints.extend(l.checkpoints.iter());
+                // The line keeps its function
+                if let Some(f) = &l.function {
+                    function = f.clone();
+                }
+            }
+
+            self.items.remove(&item);
+        }
+
+        f.borrow_mut().checkpoints.extend(checkpoints.iter());
+
+        let l = LineInfo::new(
+            nb,
+            addr_range,
+            None,
+            Some(function),
+            has_debug_info,
+            checkpoints,
+        );
+
+        self.items.insert(ProfileItem::Line(f, l));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CodeLoc, StreamDecoder, Update};
+    use crate::model::profile::{Profile, ProfileItem};
+
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[test]
+    fn decode() {
+        let mut decoder = StreamDecoder::new();
+
+        assert_eq!(decoder.decode("[checkpoint id]"), Ok(None));
+        assert_eq!(
+            decoder.decode("2 -> after_init"),
+            Ok(Some(Update::Checkpoint(2, String::from("after_init"))))
+        );
+        assert!(decoder.decode("13 [0x10;0x20] -> 2").is_err());
+        assert_eq!(decoder.decode("fl=src/main.c"), Ok(None));
+        assert_eq!(decoder.decode("fn=main"), Ok(None));
+        assert_eq!(
+            decoder.decode("13 [0x10;0x20] -> 0 2"),
+            Ok(Some(Update::CodeLoc(CodeLoc {
+                file: String::from("src/main.c"),
+                function: String::from("main"),
+                nb: 13,
+                addr_range: (0x10, 0x20),
+                checkpoints: bt_set!(0, 2),
+            })))
+        );
+        assert!(decoder.decode("13 [0x10;0x20] -> 0 2 garbage").is_err());
+    }
+
+    #[test]
+    fn apply_merges_checkpoints() {
+        let mut profile = Profile::new(BTreeSet::new(), BTreeMap::new());
+        let mut decoder = StreamDecoder::new();
+
+        let stream = "0 -> begin\nfl=main.c\nfn=main\n3 [0x1;0x2] -> 0\n5 [0x3;0x4] -> 0\n\
+                      1 -> end\n3 [0x1;0x2] -> 1\n";
+
+        for line in stream.lines() {
+            if let Some(update) = decoder.decode(line).unwrap() {
+                profile.apply(update);
+            }
+        }
+
+        assert_eq!(profile.checkpoint_name(1), Some("end"));
+        assert_eq!(profile.items.len(), 3);
+
+        let checkpoints: Vec<(usize, BTreeSet<u32>)> = profile
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ProfileItem::Line(_, l) => Some((l.nb, l.checkpoints.clone())),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(checkpoints, vec![(3, bt_set!(0, 1)), (5, bt_set!(0))]);
+
+        let f = profile.items.iter().next().unwrap().get_file_info();
+        assert_eq!(f.borrow().checkpoints, bt_set!(0, 1));
+    }
+
+    #[test]
+    fn apply_inlined_line() {
+        let mut profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let file = profile.items.iter().next().unwrap().get_file_info();
+        let file = file.borrow().path.file.clone();
+        let count = profile.items.len();
+
+        profile.apply(Update::CodeLoc(CodeLoc {
+            file,
+            function: String::from("inlined"),
+            nb: 9,
+            addr_range: (0x1089ac, 0x1089c4),
+            checkpoints: bt_set!(1),
+        }));
+
+        assert_eq!(profile.items.len(), count);
+        let line = profile
+            .items
+            .iter()
+            .find_map(|item| match item {
+                ProfileItem::Line(_, l) if l.nb == 9 => Some(l.clone()),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(line.checkpoints, bt_set!(0, 1));
+        assert_eq!(line.function.unwrap().as_str(), "main");
+    }
+}
@@ -0,0 +1,281 @@
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use super::profile::{Profile, ProfileItem};
+
+/// Number of functions listed by `Stats::largest_functions`
+pub const LARGEST_FUNCTIONS: usize = 10;
+
+/// Figures of a checkpoint
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+pub struct CheckpointStats {
+    /// Id of the checkpoint
+    pub id: u32,
+    /// Name of the checkpoint
+    pub name: String,
+    /// Lines that met the checkpoint
+    pub lines: usize,
+    /// Functions with a line that met the checkpoint
+    pub functions: usize,
+    /// Files with a line that met the checkpoint
+    pub files: usize,
+    /// Sum of the sizes of the address ranges of the lines
+    pub bytes: u64,
+    /// Lines that met no other checkpoint
+    pub unique_lines: usize,
+    /// Lines that also met the next checkpoint, by id. `None` for the last one.
+    pub shared_with_next: Option<usize>,
+}
+
+/// Size of a function
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+pub struct FunctionStats {
+    /// Name of the function
+    pub function: String,
+    /// Path of its file
+    pub file: String,
+    /// Lines of the function in the profile
+    pub lines: usize,
+    /// Sum of the sizes of the address ranges of its lines
+    pub bytes: u64,
+}
+
+/// Summary of a profile
+#[derive(Clone, PartialEq, Debug, Serialize)]
+pub struct Stats {
+    /// Files with lines
+    pub files: usize,
+    /// Functions, a function being identified by its name and file
+    pub functions: usize,
+    /// Lines of the profile
+    pub lines: usize,
+    /// Lines with debug information
+    pub debug_info_lines: usize,
+    /// Lines of the `???` file, or otherwise without debug information
+    pub unknown_lines: usize,
+    /// Share of the lines with debug information, from 0 to 1
+    pub debug_info_ratio: f64,
+    /// Figures of each checkpoint, by id
+    pub checkpoints: Vec<CheckpointStats>,
+    /// Functions with the most bytes of code, largest first
+    pub largest_functions: Vec<FunctionStats>,
+}
+
+impl Stats {
+    /// Compute the summary of a profile, without its source lines
+    pub fn new(profile: &Profile) -> Stats {
+        let mut files = BTreeSet::new();
+        let mut functions: BTreeMap<(String, String), FunctionStats> = BTreeMap::new();
+        let mut lines = 0;
+        let mut debug_info_lines = 0;
+        let mut checkpoints: BTreeMap<u32, CheckpointStats> = profile
+            .checkpoints
+            .iter()
+            .map(|(id, name)| {
+                let stats = CheckpointStats {
+                    id: *id,
+                    name: name.clone(),
+                    lines: 0,
+                    functions: 0,
+                    files: 0,
+                    bytes: 0,
+                    unique_lines: 0,
+                    shared_with_next: None,
+                };
+
+                (*id, stats)
+            })
+            .collect();
+        let ids: Vec<u32> = checkpoints.keys().cloned().collect();
+        // Functions and files of each checkpoint
+        let mut met_functions: BTreeMap<u32, BTreeSet<(String, String)>> = BTreeMap::new();
+        let mut met_files: BTreeMap<u32, BTreeSet<String>> = BTreeMap::new();
+
+        for item in profile.items.iter() {
+            let (f, l) = match item {
+                ProfileItem::File(_) => continue,
+                ProfileItem::Line(f, l) => (f.borrow(), l),
+            };
+            let file = f.path.expand().display().to_string();
+            let function = l.function.as_ref().map_or("???", |f| f.as_str());
+            let key = (file.clone(), String::from(function));
+            let bytes = l.addr_range.1.saturating_sub(l.addr_range.0);
+
+            lines += 1;
+            if l.has_debug_info {
+                debug_info_lines += 1;
+            }
+            files.insert(file.clone());
+
+            let function = functions.entry(key.clone()).or_insert(FunctionStats {
+                function: key.1.clone(),
+                file: file.clone(),
+                lines: 0,
+                bytes: 0,
+            });
+            function.lines += 1;
+            function.bytes += bytes;
+
+            for id in l.checkpoints.iter() {
+                let stats = match checkpoints.get_mut(id) {
+                    Some(stats) => stats,
+                    None => continue,
+                };
+
+                stats.lines += 1;
+                stats.bytes += bytes;
+                if l.checkpoints.len() == 1 {
+                    stats.unique_lines += 1;
+                }
+                met_functions.entry(*id).or_default().insert(key.clone());
+                met_files.entry(*id).or_default().insert(file.clone());
+            }
+
+            for pair in ids.windows(2) {
+                if l.checkpoints.contains(&pair[0]) && l.checkpoints.contains(&pair[1]) {
+                    let stats = checkpoints.get_mut(&pair[0]).unwrap();
+                    *stats.shared_with_next.get_or_insert(0) += 1;
+                }
+            }
+        }
+
+        for (i, stats) in checkpoints.values_mut().enumerate() {
+            stats.functions = met_functions.get(&stats.id).map_or(0, |f| f.len());
+            stats.files = met_files.get(&stats.id).map_or(0, |f| f.len());
+
+            if i + 1 < ids.len() && stats.shared_with_next.is_none() {
+                stats.shared_with_next = Some(0);
+            }
+        }
+
+        let function_count = functions.len();
+        let mut largest_functions: Vec<FunctionStats> = functions.into_values().collect();
+        // Stable, so that functions of the same size stay sorted by file and name
+        largest_functions.sort_by_key(|f| std::cmp::Reverse(f.bytes));
+        largest_functions.truncate(LARGEST_FUNCTIONS);
+
+        Stats {
+            files: files.len(),
+            functions: function_count,
+            lines,
+            debug_info_lines,
+            unknown_lines: lines - debug_info_lines,
+            debug_info_ratio: match lines {
+                0 => 0.0,
+                _ => debug_info_lines as f64 / lines as f64,
+            },
+            checkpoints: checkpoints.into_values().collect(),
+            largest_functions,
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "files       {}", self.files)?;
+        writeln!(f, "functions   {}", self.functions)?;
+        writeln!(f, "lines       {}", self.lines)?;
+        writeln!(
+            f,
+            "debug info  {} lines ({:.1}%), ??? {} lines",
+            self.debug_info_lines,
+            self.debug_info_ratio * 100.0,
+            self.unknown_lines
+        )?;
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:>4} {:<24} {:>8} {:>9} {:>6} {:>10} {:>8} {:>11}",
+            "id", "checkpoint", "lines", "functions", "files", "bytes", "unique", "shared next"
+        )?;
+
+        for c in self.checkpoints.iter() {
+            let shared = c
+                .shared_with_next
+                .map_or_else(|| String::from("-"), |n| n.to_string());
+
+            writeln!(
+                f,
+                "{:>4} {:<24} {:>8} {:>9} {:>6} {:>10} {:>8} {:>11}",
+                c.id, c.name, c.lines, c.functions, c.files, c.bytes, c.unique_lines, shared
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "{:>10} {:>8}  largest functions", "bytes", "lines")?;
+
+        for function in self.largest_functions.iter() {
+            writeln!(
+                f,
+                "{:>10} {:>8}  {} ({})",
+                function.bytes, function.lines, function.function, function.file
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stats;
+    use crate::model::profile::{CodeLoc, Profile, Update};
+
+    #[test]
+    fn compute_stats() {
+        let mut profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        profile.apply(Update::CodeLoc(CodeLoc {
+            file: String::from("???"),
+            function: String::from("_start"),
+            nb: 0,
+            addr_range: (0x1000, 0x1100),
+            checkpoints: bt_set!(0),
+        }));
+        profile.apply(Update::Checkpoint(2, String::from("After_hello")));
+
+        let stats = Stats::new(&profile);
+        assert_eq!((stats.files, stats.functions, stats.lines), (2, 2, 6));
+        assert_eq!((stats.debug_info_lines, stats.unknown_lines), (5, 1));
+
+        let begin = &stats.checkpoints[0];
+        assert_eq!(
+            (begin.lines, begin.functions, begin.files, begin.bytes),
+            (4, 2, 2, 24 + 5 + 88 + 0x100)
+        );
+        assert_eq!((begin.unique_lines, begin.shared_with_next), (3, Some(1)));
+
+        let hello = &stats.checkpoints[1];
+        assert_eq!((hello.unique_lines, hello.shared_with_next), (2, Some(0)));
+        assert_eq!(stats.checkpoints[2].shared_with_next, None);
+
+        assert_eq!(stats.largest_functions[0].function, "_start");
+        assert_eq!(stats.largest_functions[1].bytes, 131);
+
+        let text = stats.to_string();
+        assert!(text.contains("debug info  5 lines (83.3%), ??? 1 lines\n"));
+        assert!(text.contains(
+            "   1 Before_hello                    3         1      1        102        2           0\n"
+        ));
+        assert!(text.contains("       131        5  main (assets/test/hello/hello.c)\n"));
+    }
+
+    #[test]
+    fn reversed_addr_range() {
+        let mut profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        profile.apply(Update::CodeLoc(CodeLoc {
+            file: String::from("???"),
+            function: String::from("_start"),
+            nb: 0,
+            addr_range: (0x1100, 0x1000),
+            checkpoints: bt_set!(0),
+        }));
+
+        let stats = Stats::new(&profile);
+        assert_eq!(stats.checkpoints[0].bytes, 24 + 5 + 88);
+        assert_eq!(stats.largest_functions[0].function, "main");
+    }
+}
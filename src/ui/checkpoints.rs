@@ -2,20 +2,21 @@ use crate::model::profile::ProfileItem;
 
 use tui::{
     backend::Backend,
-    style::{Color, Style},
+    layout::Rect,
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
     widgets::Paragraph,
     Frame
 };
 
-use super::{help_widget, Panel, PanelBox};
+use super::{help_widget, theme::Theme, Panel, PanelBox, ViewItem};
 
 use std::collections::btree_set::BTreeSet;
 
-fn get_checkpoints<'a, T: AsRef<[&'a ProfileItem]>>(items: T) -> Vec<u32> {
+fn get_checkpoints(items: &[ViewItem]) -> Vec<u32> {
     let mut checkpoints = BTreeSet::new();
 
-    for item in items.as_ref() {
+    for item in items.iter().filter_map(|i| i.item()) {
         match item {
             ProfileItem::File(f) => {
                 for c in f.borrow().checkpoints.iter() {
@@ -45,29 +46,37 @@ fn number_of_digits(number: u32) -> usize {
     ret
 }
 
-fn format_header_cell<'a>(id: u32, cell_width: usize) -> Span<'a> {
-    Span::raw(format!("{:^1$}", id, cell_width))
-}
+fn format_header_cell<'a>(id: u32, cell_width: usize, focused: bool) -> Span<'a> {
+    let style = if focused {
+        Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD)
+    } else {
+        Style::default()
+    };
 
-fn format_cell<'a>(met: bool, cell_width: usize) -> Span<'a> {
-    // let status_char = if met {"◼︎"} else {"◻︎"};
-    let status_char = if met { '◼' } else { '·' };
+    Span::styled(format!("{:^1$}", id, cell_width), style)
+}
 
-    let style = if met {
-        Style::default().fg(Color::LightGreen)
+fn format_cell<'a>(met: bool, cell_width: usize, theme: &Theme) -> Span<'a> {
+    let (status_char, style) = if met {
+        (theme.met_glyph, theme.met)
     } else {
-        Style::default().fg(Color::Gray)
+        (theme.not_met_glyph, theme.not_met)
     };
 
     Span::styled(format!("{:^1$}", status_char, cell_width), style)
 }
 
-fn checkpoints_header<'a>(checkpoints: &Vec<u32>, cell_width: usize) -> Spans<'a> {
+fn checkpoints_header<'a>(
+    checkpoints: &Vec<u32>,
+    cell_width: usize,
+    focus: Option<u32>,
+) -> Spans<'a> {
     let mut spans = vec![];
 
-    if checkpoints.len() > 0 {
+    if !checkpoints.is_empty() {
         for checkpoint in checkpoints {
-            spans.push(format_header_cell(*checkpoint, cell_width));
+            let focused = focus == Some(*checkpoint);
+            spans.push(format_header_cell(*checkpoint, cell_width, focused));
         }
     }
 
@@ -78,47 +87,31 @@ fn checkpoints_line<'a>(
     item: &'a ProfileItem,
     checkpoints: &Vec<u32>,
     cell_width: u16,
+    theme: &Theme,
 ) -> Spans<'a> {
     let mut spans = vec![];
 
     for checkpoint in checkpoints {
         let met = match item {
-            ProfileItem::File(f) => {
-                f.borrow().checkpoints.iter().find(|c| *c == checkpoint) != None
-            }
-            ProfileItem::Line(_, l) => l.checkpoints.iter().find(|c| *c == checkpoint) != None,
+            ProfileItem::File(f) => f.borrow().checkpoints.iter().any(|c| c == checkpoint),
+            ProfileItem::Line(_, l) => l.checkpoints.iter().any(|c| c == checkpoint),
         };
 
-        spans.push(format_cell(met, cell_width as usize));
+        spans.push(format_cell(met, cell_width as usize, theme));
     }
 
     Spans::from(spans)
 }
 
-pub struct CheckpointPanelContext<'a> {
-    pbox: PanelBox<'a>,
+/// Layout of the checkpoint matrix, computed from the displayed rows
+struct CheckpointPanelContext {
     cell_width: u16,
     checkpoints: Vec<u32>,
 }
 
-pub struct CheckpointPanel<'a> {
-    help: Vec<(&'a str, &'a str)>,
-}
-
-impl<'a> CheckpointPanel<'a> {
-    pub fn new(help: Vec<(&'a str, &'a str)>) -> CheckpointPanel<'a> {
-        CheckpointPanel { help: help }
-    }
-}
-
-impl<'a> Panel<'a> for CheckpointPanel<'a> {
-    type Context = CheckpointPanelContext<'a>;
-
-    fn get_context<I>(&'a self, items: I, p: PanelBox<'a>) -> Self::Context
-    where
-        I: AsRef<[&'a ProfileItem]>,
-    {
-        let checkpoints = get_checkpoints(&items);
+impl CheckpointPanelContext {
+    fn new(items: &[ViewItem], width: u16) -> CheckpointPanelContext {
+        let checkpoints = get_checkpoints(items);
         let max_id = if let Some(id) = checkpoints.last() {
             *id
         } else {
@@ -127,55 +120,85 @@ impl<'a> Panel<'a> for CheckpointPanel<'a> {
         let max_digits = number_of_digits(max_id);
 
         let cell_min_width = max_digits + 2;
-        let width = p.body.block.inner(p.body.rect).width;
 
-        let cell_width = if checkpoints.len() > 0 {
+        let cell_width = if !checkpoints.is_empty() {
             usize::max(cell_min_width, width as usize / checkpoints.len()) as u16
         } else {
             0
         };
 
         CheckpointPanelContext {
-            pbox: p,
-            cell_width: cell_width,
-            checkpoints: checkpoints,
+            cell_width,
+            checkpoints,
         }
     }
+}
 
-    fn render_header<B, I>(&'a self, f: &mut Frame<B>, items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>,
-    {
-        let checkpoints = get_checkpoints(items);
-        let header_line = checkpoints_header(&checkpoints, ctx.cell_width as usize);
-        let p = Paragraph::new(Text::from(header_line)).block(ctx.pbox.header.block.clone());
+/// Cells of the checkpoint ids in a header drawn in `header`
+pub fn header_cells(items: &[ViewItem], header: Rect) -> Vec<(u32, Rect)> {
+    let ctx = CheckpointPanelContext::new(items, header.width);
+
+    ctx.checkpoints
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let x = header.x as usize + i * ctx.cell_width as usize;
+            (*id, x)
+        })
+        .filter(|(_, x)| *x < header.right() as usize)
+        .map(|(id, x)| {
+            let width = u16::min(ctx.cell_width, header.right() - x as u16);
+            (id, Rect::new(x as u16, header.y, width, 1))
+        })
+        .collect()
+}
 
-        f.render_widget(p, ctx.pbox.header.rect);
+pub struct CheckpointPanel<'a> {
+    help: Vec<(&'a str, &'a str)>,
+    /// Checkpoint in focus, highlighted in the header
+    focus: Option<u32>,
+    theme: Theme,
+}
+
+impl<'a> CheckpointPanel<'a> {
+    pub fn new(
+        help: Vec<(&'a str, &'a str)>,
+        focus: Option<u32>,
+        theme: Theme,
+    ) -> CheckpointPanel<'a> {
+        CheckpointPanel { help, focus, theme }
     }
+}
+
+impl<'a, 'i, B: Backend> Panel<'i, B> for CheckpointPanel<'a> {
+    fn render_header(&self, f: &mut Frame<B>, items: &[ViewItem<'i>], pbox: &PanelBox<'i>) {
+        let ctx = CheckpointPanelContext::new(items, pbox.header.rect.width);
+        let header_line = checkpoints_header(&ctx.checkpoints, ctx.cell_width as usize, self.focus);
+        let p = Paragraph::new(Text::from(header_line)).block(pbox.header.block.clone());
 
-    fn render_body<B, I>(&'a self, f: &mut Frame<B>, items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>,
-    {
+        f.render_widget(p, pbox.header.rect);
+    }
+
+    fn render_body(&self, f: &mut Frame<B>, items: &[ViewItem<'i>], pbox: &PanelBox<'i>) {
+        let ctx = CheckpointPanelContext::new(items, pbox.body.block.inner(pbox.body.rect).width);
         let mut checkpoint_lines: Vec<Spans> = vec![];
 
-        for item in items.as_ref().clone() {
-            checkpoint_lines.push(checkpoints_line(item, &ctx.checkpoints, ctx.cell_width));
+        for item in items {
+            checkpoint_lines.push(match item {
+                ViewItem::Item(item) => {
+                    checkpoints_line(item, &ctx.checkpoints, ctx.cell_width, &self.theme)
+                }
+                ViewItem::Fold(_) => Spans::from(vec![]),
+            });
         }
 
-        let p = Paragraph::new(Text::from(checkpoint_lines)).block(ctx.pbox.body.block.clone());
-        f.render_widget(p, ctx.pbox.body.rect);
+        let p = Paragraph::new(Text::from(checkpoint_lines)).block(pbox.body.block.clone());
+        f.render_widget(p, pbox.body.rect);
     }
 
-    fn render_help<B, I>(&'a self, f: &mut Frame<B>, _items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>,
-    {
-        let w = help_widget(&self.help).block(ctx.pbox.footer.block.clone());
+    fn render_help(&self, f: &mut Frame<B>, _items: &[ViewItem<'i>], pbox: &PanelBox<'i>) {
+        let w = help_widget(&self.help).block(pbox.footer.block.clone());
 
-        f.render_widget(w, ctx.pbox.footer.rect);
+        f.render_widget(w, pbox.footer.rect);
     }
 }
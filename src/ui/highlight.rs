@@ -0,0 +1,475 @@
+use tui::{
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+
+/// Languages the source panel knows how to highlight
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    C,
+    Cpp,
+    Rust,
+    Asm,
+}
+
+const C_KEYWORDS: &[&str] = &[
+    "auto", "break", "case", "const", "continue", "default", "do", "else", "enum", "extern", "for",
+    "goto", "if", "inline", "register", "restrict", "return", "sizeof", "static", "struct",
+    "switch", "typedef", "union", "volatile", "while",
+];
+
+const CPP_KEYWORDS: &[&str] = &[
+    "catch",
+    "class",
+    "constexpr",
+    "delete",
+    "explicit",
+    "friend",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "nullptr",
+    "operator",
+    "override",
+    "private",
+    "protected",
+    "public",
+    "template",
+    "this",
+    "throw",
+    "try",
+    "typename",
+    "using",
+    "virtual",
+];
+
+const C_TYPES: &[&str] = &[
+    "bool",
+    "char",
+    "double",
+    "float",
+    "int",
+    "long",
+    "short",
+    "signed",
+    "unsigned",
+    "void",
+    "size_t",
+    "ssize_t",
+    "pid_t",
+    "int8_t",
+    "int16_t",
+    "int32_t",
+    "int64_t",
+    "uint8_t",
+    "uint16_t",
+    "uint32_t",
+    "uint64_t",
+    "uintptr_t",
+    "FILE",
+];
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+const RUST_TYPES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64",
+    "i128", "isize", "f32", "f64", "String", "Vec", "Option", "Result", "Box", "Rc", "Arc",
+];
+
+/// Token classes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Token {
+    Plain,
+    Keyword,
+    Type,
+    String,
+    Number,
+    Comment,
+    Preprocessor,
+    Macro,
+    Label,
+    Register,
+}
+
+/// Styles used for each token class.
+/// Only foreground colours and modifiers are set, so that they can be patched
+/// on top of the style of the line (cursor, checkpoint focus...).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SyntaxTheme {
+    pub keyword: Style,
+    pub type_name: Style,
+    pub string: Style,
+    pub number: Style,
+    pub comment: Style,
+    pub preprocessor: Style,
+    pub macro_name: Style,
+    pub label: Style,
+    pub register: Style,
+}
+
+impl Default for SyntaxTheme {
+    fn default() -> Self {
+        SyntaxTheme {
+            keyword: Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+            type_name: Style::default().fg(Color::Cyan),
+            string: Style::default().fg(Color::Yellow),
+            number: Style::default().fg(Color::LightRed),
+            comment: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+            preprocessor: Style::default().fg(Color::LightBlue),
+            macro_name: Style::default().fg(Color::LightBlue),
+            label: Style::default()
+                .fg(Color::LightYellow)
+                .add_modifier(Modifier::BOLD),
+            register: Style::default().fg(Color::Cyan),
+        }
+    }
+}
+
+impl SyntaxTheme {
+    pub fn style(&self, token: Token) -> Style {
+        match token {
+            Token::Plain => Style::default(),
+            Token::Keyword => self.keyword,
+            Token::Type => self.type_name,
+            Token::String => self.string,
+            Token::Number => self.number,
+            Token::Comment => self.comment,
+            Token::Preprocessor => self.preprocessor,
+            Token::Macro => self.macro_name,
+            Token::Label => self.label,
+            Token::Register => self.register,
+        }
+    }
+}
+
+impl Language {
+    /// Guess the language of a source file from its extension
+    pub fn from_file_name(file: &str) -> Option<Language> {
+        let ext = std::path::Path::new(file).extension()?.to_str()?;
+
+        match ext {
+            "c" | "h" => Some(Language::C),
+            "cc" | "cpp" | "cxx" | "c++" | "C" | "hh" | "hpp" | "hxx" | "H" | "inl" => {
+                Some(Language::Cpp)
+            }
+            "rs" => Some(Language::Rust),
+            "s" | "S" | "asm" => Some(Language::Asm),
+            _ => None,
+        }
+    }
+
+    fn keywords(&self) -> (&'static [&'static str], &'static [&'static str]) {
+        match self {
+            Language::C => (C_KEYWORDS, &[]),
+            Language::Cpp => (C_KEYWORDS, CPP_KEYWORDS),
+            Language::Rust => (RUST_KEYWORDS, &[]),
+            Language::Asm => (&[], &[]),
+        }
+    }
+
+    fn types(&self) -> &'static [&'static str] {
+        match self {
+            Language::C | Language::Cpp => C_TYPES,
+            Language::Rust => RUST_TYPES,
+            Language::Asm => &[],
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Index of the first char after the quoted literal starting at `start`
+fn end_of_quoted(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    let mut i = start + 1;
+
+    while i < chars.len() {
+        if chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+
+        if chars[i] == quote {
+            return i + 1;
+        }
+
+        i += 1;
+    }
+
+    chars.len()
+}
+
+fn end_of_word(chars: &[char], start: usize, accept: fn(char) -> bool) -> usize {
+    let mut i = start;
+
+    while i < chars.len() && accept(chars[i]) {
+        i += 1;
+    }
+
+    i
+}
+
+fn tokenize_c_like(chars: &[char], lang: Language) -> Vec<(Token, usize, usize)> {
+    let mut tokens = vec![];
+    let (keywords, extra_keywords) = lang.keywords();
+    let types = lang.types();
+
+    // Continuation of a block comment. We highlight line by line, so this is a heuristic.
+    let first = chars.iter().position(|c| !c.is_whitespace());
+    if let Some(first) = first {
+        let next = chars.get(first + 1).cloned();
+        if chars[first] == '*' && (next.is_none() || next == Some(' ') || next == Some('/')) {
+            return vec![(Token::Comment, 0, chars.len())];
+        }
+
+        if lang != Language::Rust && chars[first] == '#' {
+            let end = end_of_word(chars, first + 1, |c| c.is_whitespace());
+            let end = end_of_word(chars, end, is_ident_char);
+            tokens.push((Token::Preprocessor, 0, end));
+        }
+    }
+
+    let mut i = tokens.last().map_or(0, |t| t.2);
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).cloned();
+
+        let (token, end) = if c == '/' && next == Some('/') {
+            (Token::Comment, chars.len())
+        } else if c == '/' && next == Some('*') {
+            let mut end = i + 3;
+            while end < chars.len() && !(chars[end - 1] == '*' && chars[end] == '/') {
+                end += 1;
+            }
+            (Token::Comment, usize::min(end + 1, chars.len()))
+        } else if c == '"' {
+            (Token::String, end_of_quoted(chars, i))
+        } else if c == '\'' {
+            let is_char = lang != Language::Rust
+                || next == Some('\\')
+                || chars.get(i + 2).cloned() == Some('\'');
+
+            if is_char {
+                (Token::String, end_of_quoted(chars, i))
+            } else {
+                (Token::Plain, end_of_word(chars, i + 1, is_ident_char))
+            }
+        } else if c == '#' && lang == Language::Rust && (next == Some('[') || next == Some('!')) {
+            let mut end = i;
+            while end < chars.len() && chars[end] != ']' {
+                end += 1;
+            }
+            (Token::Preprocessor, usize::min(end + 1, chars.len()))
+        } else if c.is_ascii_digit() {
+            let end = end_of_word(chars, i, |c| is_ident_char(c) || c == '.');
+            (Token::Number, end)
+        } else if is_ident_start(c) {
+            let end = end_of_word(chars, i, is_ident_char);
+            let word: String = chars[i..end].iter().collect();
+
+            if keywords.contains(&word.as_str()) || extra_keywords.contains(&word.as_str()) {
+                (Token::Keyword, end)
+            } else if types.contains(&word.as_str()) {
+                (Token::Type, end)
+            } else if lang == Language::Rust && chars.get(end) == Some(&'!') {
+                (Token::Macro, end + 1)
+            } else if lang != Language::Rust
+                && word.len() > 1
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_uppercase() || c == '_' || c.is_ascii_digit())
+            {
+                (Token::Macro, end)
+            } else {
+                (Token::Plain, end)
+            }
+        } else {
+            (Token::Plain, i + 1)
+        };
+
+        tokens.push((token, i, end));
+        i = end;
+    }
+
+    tokens
+}
+
+fn tokenize_asm(chars: &[char]) -> Vec<(Token, usize, usize)> {
+    let mut tokens = vec![];
+    let mut mnemonic_seen = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).cloned();
+
+        let (token, end) = if c == '#' || c == ';' || (c == '/' && next == Some('/')) {
+            (Token::Comment, chars.len())
+        } else if c == '"' || c == '\'' {
+            (Token::String, end_of_quoted(chars, i))
+        } else if c == '%' {
+            (Token::Register, end_of_word(chars, i + 1, is_ident_char))
+        } else if c == '$'
+            || c.is_ascii_digit()
+            || (c == '-' && next.is_some_and(|n| n.is_ascii_digit()))
+        {
+            (Token::Number, end_of_word(chars, i + 1, is_ident_char))
+        } else if c == '.' || is_ident_start(c) {
+            let end = end_of_word(chars, i + 1, |c| is_ident_char(c) || c == '.');
+
+            if chars.get(end) == Some(&':') {
+                (Token::Label, end + 1)
+            } else if c == '.' {
+                mnemonic_seen = true;
+                (Token::Preprocessor, end)
+            } else if !mnemonic_seen {
+                mnemonic_seen = true;
+                (Token::Keyword, end)
+            } else {
+                (Token::Plain, end)
+            }
+        } else {
+            (Token::Plain, i + 1)
+        };
+
+        tokens.push((token, i, end));
+        i = end;
+    }
+
+    tokens
+}
+
+/// Split a source line into tokens.
+/// Each token is given as its class and its char range in the line.
+/// Highlighting is done on a single line at a time, without any state carried between lines.
+pub fn tokenize(line: &str, lang: Language) -> Vec<(Token, String)> {
+    let chars: Vec<char> = line.chars().collect();
+
+    let tokens = match lang {
+        Language::Asm => tokenize_asm(&chars),
+        _ => tokenize_c_like(&chars, lang),
+    };
+
+    // Merge adjacent tokens of the same class to limit the number of spans
+    let mut ret: Vec<(Token, String)> = vec![];
+
+    for (token, start, end) in tokens {
+        let s: String = chars[start..end].iter().collect();
+
+        match ret.last_mut() {
+            Some((t, prev)) if *t == token => prev.push_str(&s),
+            _ => ret.push((token, s)),
+        }
+    }
+
+    ret
+}
+
+/// Highlight a source line.
+/// `base` is the style of the whole line, token styles are patched on top of it.
+pub fn highlight<'a>(
+    line: &str,
+    lang: Option<Language>,
+    theme: &SyntaxTheme,
+    base: Style,
+) -> Vec<Span<'a>> {
+    match lang {
+        None => vec![Span::styled(String::from(line), base)],
+        Some(lang) => tokenize(line, lang)
+            .into_iter()
+            .map(|(token, s)| Span::styled(s, base.patch(theme.style(token))))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tokenize, Language, Token};
+
+    #[test]
+    fn language_from_file_name() {
+        assert_eq!(Language::from_file_name("hello.c"), Some(Language::C));
+        assert_eq!(Language::from_file_name("a/b.hpp"), Some(Language::Cpp));
+        assert_eq!(Language::from_file_name("main.rs"), Some(Language::Rust));
+        assert_eq!(Language::from_file_name("start.S"), Some(Language::Asm));
+        assert_eq!(Language::from_file_name("???"), None);
+        assert_eq!(Language::from_file_name("Makefile"), None);
+    }
+
+    #[test]
+    fn tokenize_c() {
+        let tokens = tokenize("    pid_t pid = fork(); // fork", Language::C);
+
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::Plain, "    ".to_string()),
+                (Token::Type, "pid_t".to_string()),
+                (Token::Plain, " pid = fork(); ".to_string()),
+                (Token::Comment, "// fork".to_string()),
+            ]
+        );
+
+        let tokens = tokenize("#include \"valgrind.h\"", Language::C);
+
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::Preprocessor, "#include".to_string()),
+                (Token::Plain, " ".to_string()),
+                (Token::String, "\"valgrind.h\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenize_rust() {
+        let tokens = tokenize(
+            "fn f<'a>(x: &'a str) { println!(\"{}\", 'c'); }",
+            Language::Rust,
+        );
+        let classes: Vec<Token> = tokens.iter().map(|t| t.0).collect();
+
+        assert_eq!(tokens[0], (Token::Keyword, "fn".to_string()));
+        assert!(tokens.contains(&(Token::Macro, "println!".to_string())));
+        assert!(tokens.contains(&(Token::String, "'c'".to_string())));
+        assert!(!classes.contains(&Token::Comment));
+    }
+
+    #[test]
+    fn tokenize_asm() {
+        let tokens = tokenize("loop:  movq $1, %rax # one", Language::Asm);
+
+        assert_eq!(
+            tokens,
+            vec![
+                (Token::Label, "loop:".to_string()),
+                (Token::Plain, "  ".to_string()),
+                (Token::Keyword, "movq".to_string()),
+                (Token::Plain, " ".to_string()),
+                (Token::Number, "$1".to_string()),
+                (Token::Plain, ", ".to_string()),
+                (Token::Register, "%rax".to_string()),
+                (Token::Plain, " ".to_string()),
+                (Token::Comment, "# one".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+use serde::Deserialize;
+use std::{convert::TryFrom, fmt, str::FromStr};
+use tui::layout::{Constraint, Rect};
+
+/// Panels that can be displayed, from left to right
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PanelKind {
+    Checkpoints,
+    Addresses,
+    Source,
+}
+
+impl PanelKind {
+    pub const ALL: [PanelKind; 3] = [
+        PanelKind::Checkpoints,
+        PanelKind::Addresses,
+        PanelKind::Source,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PanelKind::Checkpoints => "checkpoints",
+            PanelKind::Addresses => "addresses",
+            PanelKind::Source => "source",
+        }
+    }
+}
+
+/// Width of a panel
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(try_from = "SizeValue")]
+pub enum PanelSize {
+    /// Take the remaining space
+    Fill,
+    /// Fixed number of columns
+    Length(u16),
+    /// Percentage of the screen width
+    Percentage(u16),
+}
+
+const LENGTH_STEP: u16 = 2;
+const PERCENTAGE_STEP: u16 = 5;
+const MIN_LENGTH: u16 = 8;
+
+impl PanelSize {
+    pub fn constraint(&self) -> Constraint {
+        match *self {
+            PanelSize::Fill => Constraint::Min(0),
+            PanelSize::Length(n) => Constraint::Length(n),
+            PanelSize::Percentage(p) => Constraint::Percentage(p),
+        }
+    }
+
+    /// Same kind of size, `columns` wide out of `total` columns
+    pub fn with_width(&self, columns: u16, total: u16) -> PanelSize {
+        match *self {
+            PanelSize::Fill => PanelSize::Fill,
+            PanelSize::Length(_) => PanelSize::Length(u16::max(columns, MIN_LENGTH)),
+            PanelSize::Percentage(_) => {
+                let p = (columns as u32 * 100 / u32::max(total as u32, 1)) as u16;
+                PanelSize::Percentage(p.clamp(PERCENTAGE_STEP, 95))
+            }
+        }
+    }
+
+    /// Grow (or shrink if `steps` is negative) the panel.
+    /// Filling panels are not resized: they get whatever space the other ones leave.
+    pub fn resized(&self, steps: i16) -> PanelSize {
+        match *self {
+            PanelSize::Fill => PanelSize::Fill,
+            PanelSize::Length(n) => {
+                let n = n as i32 + (steps * LENGTH_STEP as i16) as i32;
+                PanelSize::Length(n.clamp(MIN_LENGTH as i32, u16::MAX as i32) as u16)
+            }
+            PanelSize::Percentage(p) => {
+                let p = p as i32 + (steps * PERCENTAGE_STEP as i16) as i32;
+                PanelSize::Percentage(p.clamp(PERCENTAGE_STEP as i32, 95) as u16)
+            }
+        }
+    }
+}
+
+impl FromStr for PanelSize {
+    type Err = String;
+
+    /// Parse "fill", a number of columns ("30") or a percentage ("50%")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || format!("invalid panel size {:?}, expected \"fill\", N or \"N%\"", s);
+
+        if s == "fill" {
+            return Ok(PanelSize::Fill);
+        }
+
+        if let Some(p) = s.strip_suffix('%') {
+            let p: u16 = p.trim().parse().map_err(|_| invalid())?;
+
+            if p == 0 || p > 100 {
+                return Err(format!(
+                    "invalid panel size {:?}, percentages go from 1 to 100",
+                    s
+                ));
+            }
+
+            return Ok(PanelSize::Percentage(p));
+        }
+
+        s.parse().map(PanelSize::Length).map_err(|_| invalid())
+    }
+}
+
+impl fmt::Display for PanelSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PanelSize::Fill => write!(f, "fill"),
+            PanelSize::Length(n) => write!(f, "{}", n),
+            PanelSize::Percentage(p) => write!(f, "{}%", p),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SizeValue {
+    Length(u16),
+    Text(String),
+}
+
+impl TryFrom<SizeValue> for PanelSize {
+    type Error = String;
+
+    fn try_from(value: SizeValue) -> Result<Self, Self::Error> {
+        match value {
+            SizeValue::Length(n) => Ok(PanelSize::Length(n)),
+            SizeValue::Text(s) => s.parse(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PanelSlot {
+    pub panel: PanelKind,
+    pub size: PanelSize,
+    #[serde(default = "visible_by_default")]
+    pub visible: bool,
+}
+
+fn visible_by_default() -> bool {
+    true
+}
+
+/// Order, width and visibility of the panels
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PanelLayout {
+    slots: Vec<PanelSlot>,
+    selected: usize,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        PanelLayout {
+            slots: vec![
+                PanelSlot {
+                    panel: PanelKind::Checkpoints,
+                    size: PanelSize::Fill,
+                    visible: true,
+                },
+                PanelSlot {
+                    panel: PanelKind::Addresses,
+                    size: PanelSize::Length(30),
+                    visible: true,
+                },
+                PanelSlot {
+                    panel: PanelKind::Source,
+                    size: PanelSize::Percentage(50),
+                    visible: true,
+                },
+            ],
+            selected: 0,
+        }
+    }
+}
+
+impl PanelLayout {
+    /// Build a layout from a list of panels.
+    /// Panels that are not listed are appended, hidden.
+    pub fn new(slots: Vec<PanelSlot>) -> Result<PanelLayout, String> {
+        let mut slots = slots;
+
+        for kind in PanelKind::ALL.iter() {
+            match slots.iter().filter(|s| s.panel == *kind).count() {
+                0 => {
+                    let default = PanelLayout::default();
+                    let mut slot = *default.slot(*kind).unwrap();
+                    slot.visible = false;
+                    slots.push(slot);
+                }
+                1 => {}
+                _ => return Err(format!("panel {:?} is listed more than once", kind.name())),
+            }
+        }
+
+        let selected = match slots.iter().position(|s| s.visible) {
+            Some(selected) => selected,
+            None => return Err(String::from("at least one panel must be visible")),
+        };
+
+        Ok(PanelLayout { slots, selected })
+    }
+
+    fn slot(&self, kind: PanelKind) -> Option<&PanelSlot> {
+        self.slots.iter().find(|s| s.panel == kind)
+    }
+
+    /// Visible panels, from left to right
+    pub fn visible(&self) -> impl Iterator<Item = &PanelSlot> {
+        self.slots.iter().filter(|s| s.visible)
+    }
+
+    pub fn selected(&self) -> Option<PanelKind> {
+        self.slots
+            .get(self.selected)
+            .filter(|s| s.visible)
+            .map(|s| s.panel)
+    }
+
+    pub fn select(&mut self, kind: PanelKind) {
+        if let Some(i) = self.slots.iter().position(|s| s.panel == kind && s.visible) {
+            self.selected = i;
+        }
+    }
+
+    pub fn size(&self, kind: PanelKind) -> Option<PanelSize> {
+        self.slot(kind).map(|s| s.size)
+    }
+
+    /// Set the width of a panel to `columns` out of `total` columns.
+    /// Returns false if the panel fills the remaining space and cannot be resized.
+    pub fn resize_to(&mut self, kind: PanelKind, columns: u16, total: u16) -> bool {
+        match self.slots.iter_mut().find(|s| s.panel == kind) {
+            Some(slot) if slot.size != PanelSize::Fill => {
+                slot.size = slot.size.with_width(columns, total);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Select the next visible panel
+    pub fn select_next(&mut self) {
+        for i in 1..=self.slots.len() {
+            let candidate = (self.selected + i) % self.slots.len();
+
+            if self.slots[candidate].visible {
+                self.selected = candidate;
+                return;
+            }
+        }
+    }
+
+    /// Show or hide a panel.
+    /// Returns false if the panel is the last visible one, which cannot be hidden.
+    pub fn toggle(&mut self, kind: PanelKind) -> bool {
+        let visible_count = self.visible().count();
+
+        if let Some(slot) = self.slots.iter_mut().find(|s| s.panel == kind) {
+            if slot.visible && visible_count == 1 {
+                return false;
+            }
+
+            slot.visible = !slot.visible;
+        }
+
+        if !self.slots[self.selected].visible {
+            self.select_next();
+        }
+
+        true
+    }
+
+    /// Grow (or shrink) the selected panel.
+    /// Returns false if the selected panel fills the remaining space and cannot be resized.
+    pub fn resize_selected(&mut self, steps: i16) -> bool {
+        let slot = &mut self.slots[self.selected];
+        slot.size = slot.size.resized(steps);
+
+        slot.size != PanelSize::Fill
+    }
+
+    /// Swap the selected panel with its next visible neighbour on the left (`-1`) or
+    /// on the right (`1`)
+    pub fn move_selected(&mut self, direction: isize) {
+        let mut i = self.selected as isize + direction;
+
+        while i >= 0 && (i as usize) < self.slots.len() {
+            if self.slots[i as usize].visible {
+                self.slots.swap(self.selected, i as usize);
+                self.selected = i as usize;
+                return;
+            }
+
+            i += direction;
+        }
+    }
+}
+
+/// Where a panel has been drawn, recorded at each draw for mouse handling
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PanelArea {
+    pub panel: PanelKind,
+    pub rect: Rect,
+    pub header: Rect,
+    pub body: Rect,
+    /// Checkpoint ids displayed in the header, and their cells
+    pub header_cells: Vec<(u32, Rect)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PanelKind, PanelLayout, PanelSize, PanelSlot};
+
+    #[test]
+    fn parse_size() {
+        assert_eq!("fill".parse(), Ok(PanelSize::Fill));
+        assert_eq!("30".parse(), Ok(PanelSize::Length(30)));
+        assert_eq!(" 50% ".parse(), Ok(PanelSize::Percentage(50)));
+        assert!("150%".parse::<PanelSize>().is_err());
+        assert!("wide".parse::<PanelSize>().is_err());
+    }
+
+    #[test]
+    fn resize() {
+        assert_eq!(PanelSize::Length(30).resized(1), PanelSize::Length(32));
+        assert_eq!(PanelSize::Length(10).resized(-5), PanelSize::Length(8));
+        assert_eq!(
+            PanelSize::Percentage(50).resized(-1),
+            PanelSize::Percentage(45)
+        );
+        assert_eq!(PanelSize::Fill.resized(3), PanelSize::Fill);
+
+        assert_eq!(
+            PanelSize::Length(30).with_width(3, 100),
+            PanelSize::Length(8)
+        );
+        assert_eq!(
+            PanelSize::Percentage(50).with_width(40, 200),
+            PanelSize::Percentage(20)
+        );
+    }
+
+    #[test]
+    fn toggle_and_move() {
+        let mut layout = PanelLayout::default();
+
+        layout.toggle(PanelKind::Addresses);
+        let visible: Vec<PanelKind> = layout.visible().map(|s| s.panel).collect();
+        assert_eq!(visible, vec![PanelKind::Checkpoints, PanelKind::Source]);
+
+        // Moving right skips the hidden panel
+        layout.move_selected(1);
+        let order: Vec<PanelKind> = layout.slots.iter().map(|s| s.panel).collect();
+        assert_eq!(
+            order,
+            vec![
+                PanelKind::Source,
+                PanelKind::Addresses,
+                PanelKind::Checkpoints
+            ]
+        );
+        assert_eq!(layout.selected(), Some(PanelKind::Checkpoints));
+
+        // The last visible panel stays visible
+        assert!(layout.toggle(PanelKind::Source));
+        assert!(!layout.toggle(PanelKind::Checkpoints));
+        assert_eq!(layout.visible().count(), 1);
+    }
+
+    #[test]
+    fn missing_and_duplicated_panels() {
+        let source = PanelSlot {
+            panel: PanelKind::Source,
+            size: PanelSize::Fill,
+            visible: true,
+        };
+
+        let layout = PanelLayout::new(vec![source]).unwrap();
+        assert_eq!(layout.slots.len(), 3);
+        assert_eq!(layout.visible().count(), 1);
+
+        assert!(PanelLayout::new(vec![source, source]).is_err());
+
+        let hidden = PanelSlot {
+            visible: false,
+            ..source
+        };
+        assert!(PanelLayout::new(vec![hidden]).is_err());
+        assert!(PanelLayout::new(vec![]).is_err());
+    }
+}
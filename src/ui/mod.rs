@@ -7,12 +7,23 @@ use tui::{
     Frame,
 };
 
-use crate::controller::App;
+use crate::controller::{fold::Row, App};
 use crate::model::profile::ProfileItem;
 
 mod addr_range;
 mod checkpoints;
+mod highlight;
+pub mod layout;
+mod plain;
 mod source;
+mod stats;
+mod status;
+mod text;
+pub mod theme;
+
+use layout::{PanelArea, PanelKind, PanelSlot};
+pub use plain::{ansi_rows, plain_rows, plain_view};
+pub use text::display_width;
 
 pub fn help_widget<'a, T: AsRef<[(&'a str, &'a str)]>>(items: T) -> Paragraph<'a> {
     let block = Block::default()
@@ -31,6 +42,22 @@ pub fn help_widget<'a, T: AsRef<[(&'a str, &'a str)]>>(items: T) -> Paragraph<'a
     Paragraph::new(Spans::from(spans)).block(block)
 }
 
+/// A row as seen by the panels
+pub enum ViewItem<'a> {
+    Item(&'a ProfileItem),
+    /// Number of folded lines
+    Fold(usize),
+}
+
+impl<'a> ViewItem<'a> {
+    pub fn item(&self) -> Option<&'a ProfileItem> {
+        match self {
+            ViewItem::Item(item) => Some(item),
+            ViewItem::Fold(_) => None,
+        }
+    }
+}
+
 pub struct PanelPart<'a> {
     rect: Rect,
     block: Block<'a>,
@@ -42,32 +69,21 @@ pub struct PanelBox<'a> {
     footer: PanelPart<'a>,
 }
 
-pub trait Panel<'a> {
-    type Context;
-
-    fn get_context<I>(&'a self, items: I, p: PanelBox<'a>) -> Self::Context
-    where
-        I: AsRef<[&'a ProfileItem]>;
-    fn render_header<B, I>(&'a self, f: &mut Frame<B>, items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>;
-    fn render_body<B, I>(&'a self, f: &mut Frame<B>, items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>;
-    fn render_help<B, I>(&'a self, f: &mut Frame<B>, items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>;
+/// A panel renders one column of information about the displayed rows.
+/// The trait is object safe, so that the panels to draw can be chosen at runtime.
+pub trait Panel<'a, B: Backend> {
+    fn render_header(&self, f: &mut Frame<B>, items: &[ViewItem<'a>], pbox: &PanelBox<'a>);
+    fn render_body(&self, f: &mut Frame<B>, items: &[ViewItem<'a>], pbox: &PanelBox<'a>);
+    fn render_help(&self, f: &mut Frame<B>, items: &[ViewItem<'a>], pbox: &PanelBox<'a>);
 }
 
-pub fn render_panel<'a, P, B, T>(p: &'a mut P, f: &mut Frame<B>, rect: Rect, items: T)
-where
-    P: Panel<'a>,
-    B: tui::backend::Backend,
-    T: AsRef<[&'a ProfileItem]>,
-{
+/// Render a panel and return the areas of its header and body
+pub fn render_panel<'a, B: Backend>(
+    p: &dyn Panel<'a, B>,
+    f: &mut Frame<B>,
+    rect: Rect,
+    items: &[ViewItem<'a>],
+) -> (Rect, Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -106,11 +122,47 @@ where
         },
     };
 
-    let ctx = p.get_context(&items, panel_box);
+    p.render_body(f, items, &panel_box);
+    p.render_header(f, items, &panel_box);
+    p.render_help(f, items, &panel_box);
+
+    (header_chunk, main_chunk)
+}
+
+/// True if the item met the checkpoint
+fn item_met(item: &ProfileItem, id: u32) -> bool {
+    match item {
+        ProfileItem::File(f) => f.borrow().checkpoints.contains(&id),
+        ProfileItem::Line(_, l) => l.checkpoints.contains(&id),
+    }
+}
+
+/// Apply `style` over the row at `offset` in a panel body
+fn highlight_row<B: Backend>(f: &mut Frame<B>, body: Rect, offset: usize, style: Style) {
+    if offset >= body.height as usize {
+        return;
+    }
+
+    let rect = Rect::new(body.x, body.y + offset as u16, body.width, 1);
 
-    p.render_body(f, &items, &ctx);
-    p.render_header(f, &items, &ctx);
-    p.render_help(f, &items, &ctx);
+    f.render_widget(Block::default().style(style), rect);
+}
+
+fn new_panel<'a, B: Backend>(kind: PanelKind, app: &App) -> Box<dyn Panel<'a, B>> {
+    let help = vec![("H", "Help")];
+
+    match kind {
+        PanelKind::Checkpoints => Box::new(checkpoints::CheckpointPanel::new(
+            help, app.focus, app.theme,
+        )),
+        PanelKind::Addresses => Box::new(addr_range::InstAddrPanel::new(help)),
+        PanelKind::Source => Box::new(source::SourcePanel::new(
+            help,
+            app.tab_width,
+            app.h_offset,
+            app.theme,
+        )),
+    }
 }
 
 pub fn draw<B: tui::backend::Backend>(f: &mut Frame<B>, app: &mut App) {
@@ -120,7 +172,7 @@ pub fn draw<B: tui::backend::Backend>(f: &mut Frame<B>, app: &mut App) {
             [
                 Constraint::Length(0),
                 Constraint::Min(0),
-                Constraint::Length(0),
+                Constraint::Length(1),
             ]
             .as_ref(),
         )
@@ -131,16 +183,21 @@ pub fn draw<B: tui::backend::Backend>(f: &mut Frame<B>, app: &mut App) {
     let main_chunk = vertical_chunks[1];
     let footer_chunk = vertical_chunks[2];
 
+    if let Some(stats) = &app.stats {
+        // Inside the borders
+        app.stats_height = main_chunk.height.saturating_sub(2) as usize;
+        app.stats_offset = stats::render_stats(f, main_chunk, stats, app.stats_offset, &app.theme);
+        status::render_status_bar(f, footer_chunk, app);
+        app.areas = vec![];
+        return;
+    }
+
+    let slots: Vec<PanelSlot> = app.layout.visible().cloned().collect();
+    let constraints: Vec<Constraint> = slots.iter().map(|s| s.size.constraint()).collect();
+
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Min(0),
-                Constraint::Length(30),
-                Constraint::Percentage(50),
-            ]
-            .as_ref(),
-        )
+        .constraints(constraints)
         .margin(1)
         .split(main_chunk);
 
@@ -150,52 +207,90 @@ pub fn draw<B: tui::backend::Backend>(f: &mut Frame<B>, app: &mut App) {
         .margin(0)
         .split(header_chunk);
 
-    let _footer_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(100)].as_ref())
-        .margin(0)
-        .split(footer_chunk);
-
-    let checkpoints_chunk = main_chunks[0];
-    let addr_chunk = main_chunks[1];
-    let source_chunk = main_chunks[2];
-
-    let source_block = Block::default().borders(Borders::ALL);
-
-    let h = source_block.inner(source_chunk).height;
-    app.set_height(h - 4);
-
-    let a = app.get_y_pos();
-    let b = std::cmp::min(a + (h as usize), app.items.len() - 1);
-
-    let items = &app.items[a..=b];
-
-    // let t2 = "Placeholder";
-    // let info_text = Text::from(t2);
+    if main_chunks.is_empty() {
+        return;
+    }
 
-    /*
-    let info_chunk = footer_chunks[0];
-    let info_block = Block::default()
+    let h = Block::default()
         .borders(Borders::ALL)
-        .style(Style::default().bg(Color::Red));
+        .inner(main_chunks[0])
+        .height;
+    app.set_height(h.saturating_sub(4));
 
-    let info = Paragraph::new(info_text).block(info_block);
-    */
-
-    let source_outter_block = Block::default().borders(Borders::BOTTOM | Borders::TOP);
-    f.render_widget(source_outter_block, source_chunk);
-    
-    let addr_outter_block = Block::default().borders(Borders::BOTTOM | Borders::TOP);
-    f.render_widget(addr_outter_block, addr_chunk);
-
-    let checkpoints_outter_block = Block::default().borders(Borders::BOTTOM | Borders::TOP);
-    f.render_widget(checkpoints_outter_block, checkpoints_chunk);
-
-    let mut checkpoint_panel = checkpoints::CheckpointPanel::new(vec![("H", "Help")]);
-    let mut addr_panel = addr_range::InstAddrPanel::new(vec![("H", "Help")]);
-    let mut source_panel = source::SourcePanel::new(vec![("H", "Help")]);
+    let a = app.get_y_pos();
+    let b = std::cmp::min(a + (h as usize) + 1, app.rows.len());
+
+    let items: Vec<ViewItem> = app.rows[a..b]
+        .iter()
+        .map(|row| match row {
+            Row::Item(i) => ViewItem::Item(&app.items[*i]),
+            Row::Fold { len, .. } => ViewItem::Fold(*len),
+        })
+        .collect();
+
+    let cursor = app.get_cursor_offset();
+    let flashed: Vec<usize> = app.rows[a..b]
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| app.is_flashed(row))
+        .map(|(offset, _)| offset)
+        .collect();
+    let focused: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| match (item.item(), app.focus) {
+            (Some(item), Some(id)) => item_met(item, id),
+            _ => false,
+        })
+        .map(|(offset, _)| offset)
+        .collect();
+    let selected = app.layout.selected();
+    let mut areas = vec![];
+
+    for (slot, chunk) in slots.iter().zip(main_chunks) {
+        let border_style = if Some(slot.panel) == selected {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+
+        let outter_block = Block::default()
+            .borders(Borders::BOTTOM | Borders::TOP)
+            .border_style(border_style);
+        f.render_widget(outter_block, chunk);
+
+        let panel = new_panel(slot.panel, app);
+        let (header, body) = render_panel(panel.as_ref(), f, chunk, &items);
+
+        for offset in focused.iter() {
+            highlight_row(f, body, *offset, app.theme.focus);
+        }
+
+        if slot.panel == PanelKind::Checkpoints {
+            for offset in flashed.iter() {
+                highlight_row(f, body, *offset, app.theme.flash);
+            }
+        }
+
+        highlight_row(
+            f,
+            body,
+            cursor,
+            Style::default().add_modifier(Modifier::REVERSED),
+        );
+
+        areas.push(PanelArea {
+            panel: slot.panel,
+            rect: chunk,
+            header,
+            body,
+            header_cells: match slot.panel {
+                PanelKind::Checkpoints => checkpoints::header_cells(&items, header),
+                _ => vec![],
+            },
+        });
+    }
 
-    render_panel(&mut checkpoint_panel, f, checkpoints_chunk, &items);
-    render_panel(&mut addr_panel, f, addr_chunk, &items);
-    render_panel(&mut source_panel, f, source_chunk, &items);
+    status::render_status_bar(f, footer_chunk, app);
+    app.areas = areas;
 }
@@ -0,0 +1,212 @@
+use tui::style::{Color, Modifier, Style};
+
+use crate::controller::{fold::Row, App};
+use crate::model::profile::{Profile, ProfileItem};
+
+use super::{text::expand_tabs, theme::Theme};
+
+/// The rows displayed by the application, as plain text
+pub fn plain_view(app: &App) -> String {
+    plain_rows(
+        &app.profile,
+        &app.items,
+        &app.rows,
+        app.tab_width,
+        &app.theme,
+    )
+}
+
+/// SGR parameters of a colour, `base` being 30 for the foreground and 40 for the
+/// background
+fn sgr_color(color: Color, base: u8) -> String {
+    match color {
+        Color::Reset => (base + 9).to_string(),
+        Color::Black => base.to_string(),
+        Color::Red => (base + 1).to_string(),
+        Color::Green => (base + 2).to_string(),
+        Color::Yellow => (base + 3).to_string(),
+        Color::Blue => (base + 4).to_string(),
+        Color::Magenta => (base + 5).to_string(),
+        Color::Cyan => (base + 6).to_string(),
+        Color::Gray => (base + 7).to_string(),
+        Color::DarkGray => (base + 60).to_string(),
+        Color::LightRed => (base + 61).to_string(),
+        Color::LightGreen => (base + 62).to_string(),
+        Color::LightYellow => (base + 63).to_string(),
+        Color::LightBlue => (base + 64).to_string(),
+        Color::LightMagenta => (base + 65).to_string(),
+        Color::LightCyan => (base + 66).to_string(),
+        Color::White => (base + 67).to_string(),
+        Color::Indexed(i) => format!("{};5;{}", base + 8, i),
+        Color::Rgb(r, g, b) => format!("{};2;{};{};{}", base + 8, r, g, b),
+    }
+}
+
+/// Text with the ANSI escape codes of a style, or the text alone if there are none
+fn ansi(style: Style, text: &str) -> String {
+    let mut params = vec![];
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+    ];
+
+    for (modifier, param) in modifiers.iter() {
+        if style.add_modifier.contains(*modifier) {
+            params.push(param.to_string());
+        }
+    }
+    params.extend(style.fg.map(|c| sgr_color(c, 30)));
+    params.extend(style.bg.map(|c| sgr_color(c, 40)));
+
+    if params.is_empty() || text.is_empty() {
+        return String::from(text);
+    }
+
+    format!("\x1b[{}m{}\x1b[0m", params.join(";"), text)
+}
+
+/// One line of text per row: the checkpoint matrix, the address range and the source
+/// line
+pub fn plain_rows(
+    profile: &Profile,
+    items: &[ProfileItem],
+    rows: &[Row],
+    tab_width: usize,
+    theme: &Theme,
+) -> String {
+    render_rows(profile, items, rows, tab_width, theme, false)
+}
+
+/// Like `plain_rows`, coloured with ANSI escape codes
+pub fn ansi_rows(
+    profile: &Profile,
+    items: &[ProfileItem],
+    rows: &[Row],
+    tab_width: usize,
+    theme: &Theme,
+) -> String {
+    render_rows(profile, items, rows, tab_width, theme, true)
+}
+
+fn render_rows(
+    profile: &Profile,
+    items: &[ProfileItem],
+    rows: &[Row],
+    tab_width: usize,
+    theme: &Theme,
+    colored: bool,
+) -> String {
+    let style = |style: Style, text: &str| match colored {
+        true => ansi(style, text),
+        false => String::from(text),
+    };
+    let checkpoints: Vec<u32> = profile.checkpoints.keys().cloned().collect();
+    let mut out = String::new();
+
+    let names: Vec<String> = profile
+        .checkpoints
+        .iter()
+        .map(|(id, name)| format!("{}:{}", id, name))
+        .collect();
+    out.push_str(&format!("# checkpoints {}\n", names.join(" ")));
+
+    for row in rows.iter() {
+        let item = match row {
+            Row::Item(i) => &items[*i],
+            Row::Fold { len, .. } => {
+                let fold = format!("… {} lines …", len);
+                out.push_str(&format!("{}\n", style(theme.dimmed, &fold)));
+                continue;
+            }
+        };
+
+        match item {
+            ProfileItem::File(f) => {
+                let path = f.borrow().path.expand().display().to_string();
+                out.push_str(&format!("{}\n", style(theme.file_tag, &path)))
+            }
+            ProfileItem::Line(_, l) => {
+                let matrix: String = checkpoints
+                    .iter()
+                    .map(|c| {
+                        if l.checkpoints.contains(c) {
+                            style(theme.met, &theme.met_glyph.to_string())
+                        } else {
+                            style(theme.not_met, &theme.not_met_glyph.to_string())
+                        }
+                    })
+                    .collect();
+
+                let addr = match l.addr_range {
+                    (0, 0) => String::new(),
+                    (start, end) => format!("{:#x}-{:#x}", start, end),
+                };
+
+                let (content, content_style) = match (&l.line_content, &l.function) {
+                    (Some(content), _) => (expand_tabs(content, tab_width, 0), Style::default()),
+                    (None, Some(function)) => (format!("in function: {}", function), theme.dimmed),
+                    (None, None) => (String::new(), Style::default()),
+                };
+
+                let mut line = format!(
+                    "{} {} {}",
+                    matrix,
+                    style(theme.dimmed, &format!("{:<23}", addr)),
+                    style(theme.line_number, &format!("{:>6}", l.nb))
+                );
+
+                let content = content.trim_end();
+                if !content.is_empty() {
+                    line.push(' ');
+                    line.push_str(&style(content_style, content));
+                }
+
+                out.push_str(&line);
+                out.push('\n');
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ansi, ansi_rows, plain_rows};
+    use crate::controller::fold;
+    use crate::model::profile::{Profile, ProfileItem};
+    use crate::ui::theme::Theme;
+    use tui::style::{Color, Modifier, Style};
+
+    #[test]
+    fn render_rows() {
+        let profile = Profile::parse("assets/test/memviz.chekpoint.28516");
+        let items: Vec<ProfileItem> = profile.items.iter().cloned().collect();
+        let rows = fold::unfolded(&items);
+
+        let plain = plain_rows(&profile, &items, &rows, 4, &Theme::default());
+        assert_eq!(
+            plain.lines().nth(4),
+            Some("◼◼ 0x1089d1-0x108a29           13 in function: main")
+        );
+
+        let colored = ansi_rows(&profile, &items, &rows, 4, &Theme::monochrome());
+        assert_eq!(
+            colored.lines().nth(4),
+            Some(
+                "\x1b[1m◼\x1b[0m\x1b[1m◼\x1b[0m \x1b[3m0x1089d1-0x108a29      \x1b[0m \
+                 \x1b[3m    13\x1b[0m \x1b[3min function: main\x1b[0m"
+            )
+        );
+
+        let style = Style::default()
+            .fg(Color::Indexed(17))
+            .bg(Color::LightGreen)
+            .add_modifier(Modifier::BOLD);
+        assert_eq!(ansi(style, "x"), "\x1b[1;38;5;17;102mx\x1b[0m");
+        assert_eq!(ansi(Style::default(), "x"), "x");
+    }
+}
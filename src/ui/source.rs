@@ -2,55 +2,70 @@ use crate::model::profile::ProfileItem;
 
 use tui::{
     backend::Backend,
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Span, Spans, Text},
     widgets::Paragraph,
     Frame,
 };
 
-use super::{help_widget, Panel, PanelBox};
+use super::{
+    help_widget,
+    highlight::{highlight, Language},
+    text::{clip_spans, expand_spans_tabs},
+    theme::Theme,
+    Panel, PanelBox, ViewItem,
+};
+
+/// Width of the line number column, including the separator
+const LINE_NB_WIDTH: usize = 8;
 
-fn line_nb_col<'a>(n: usize) -> Span<'a> {
-    Span::styled(
-        format!("{:5} ", n),
-        Style::default()
-            .bg(Color::DarkGray)
-            .add_modifier(Modifier::ITALIC),
-    )
+fn line_nb_col<'a>(n: usize, theme: &Theme) -> Span<'a> {
+    Span::styled(format!("{:5} ", n), theme.line_number)
 }
 
-fn render_code_line<'a>(item: &'a ProfileItem) -> Spans<'a> {
+fn render_fold<'a>(len: usize, theme: &Theme) -> Spans<'a> {
+    Spans::from(vec![Span::styled(
+        format!("{:>5}   … {} lines …", "", len),
+        theme.dimmed,
+    )])
+}
+
+fn render_code_line<'a>(item: &'a ProfileItem, panel: &SourcePanel, width: usize) -> Spans<'a> {
     match item {
-        ProfileItem::File(f) => Spans::from(vec![
-            Span::styled(" [fl] ", Style::default().bg(Color::Green)),
-            Span::styled(
-                format!("  {:?}", f.borrow().path.expand()),
-                Style::default()
-                    .add_modifier(Modifier::BOLD)
-                    .add_modifier(Modifier::ITALIC),
-            ),
-        ]),
-        ProfileItem::Line(_, l) => {
-            if l.line_content.is_some() {
-                Spans::from(vec![
-                    line_nb_col(l.nb),
-                    Span::raw(format!("  {}", l.line_content.as_ref().unwrap())),
-                ])
+        ProfileItem::File(f) => Spans::from(clip_spans(
+            vec![
+                Span::styled(" [fl] ", panel.theme.file_tag),
+                Span::styled(
+                    format!("  {:?}", f.borrow().path.expand()),
+                    Style::default()
+                        .add_modifier(Modifier::BOLD)
+                        .add_modifier(Modifier::ITALIC),
+                ),
+            ],
+            0,
+            width,
+        )),
+        ProfileItem::Line(f, l) => {
+            if let Some(content) = &l.line_content {
+                let lang = Language::from_file_name(&f.borrow().path.file);
+                let code = highlight(content, lang, &panel.theme.syntax, Style::default());
+                let code = expand_spans_tabs(code, panel.tab_width);
+
+                let mut spans = vec![line_nb_col(l.nb, &panel.theme), Span::raw("  ")];
+                spans.extend(clip_spans(
+                    code,
+                    panel.h_offset,
+                    width.saturating_sub(LINE_NB_WIDTH),
+                ));
+
+                Spans::from(spans)
             } else {
                 Spans::from(vec![
-                    line_nb_col(l.nb),
-                    Span::styled(
-                        format!("  in function: "),
-                        Style::default()
-                            .fg(Color::Gray)
-                            .add_modifier(Modifier::ITALIC),
-                    ),
+                    line_nb_col(l.nb, &panel.theme),
+                    Span::styled("  in function: ", panel.theme.dimmed),
                     Span::styled(
                         format!("{}", l.function.as_ref().unwrap()),
-                        Style::default()
-                            .fg(Color::Gray)
-                            .add_modifier(Modifier::ITALIC)
-                            .add_modifier(Modifier::BOLD),
+                        panel.theme.dimmed.add_modifier(Modifier::BOLD),
                     ),
                 ])
             }
@@ -60,81 +75,74 @@ fn render_code_line<'a>(item: &'a ProfileItem) -> Spans<'a> {
 
 pub struct SourcePanel<'a> {
     help: Vec<(&'a str, &'a str)>,
-}
-
-pub struct SourceContext<'a> {
-    pub pbox: PanelBox<'a>,
+    theme: Theme,
+    tab_width: usize,
+    /// Number of columns of source code scrolled out on the left
+    h_offset: usize,
 }
 
 impl<'a> SourcePanel<'a> {
-    pub fn new(help: Vec<(&'a str, &'a str)>) -> SourcePanel<'a> {
-        SourcePanel { help: help }
+    pub fn new(
+        help: Vec<(&'a str, &'a str)>,
+        tab_width: usize,
+        h_offset: usize,
+        theme: Theme,
+    ) -> SourcePanel<'a> {
+        SourcePanel {
+            help,
+            theme,
+            tab_width,
+            h_offset,
+        }
     }
 }
 
-impl<'a> Panel<'a> for SourcePanel<'a> {
-    type Context = SourceContext<'a>;
-
-    fn get_context<I>(&'a self, _items: I, p: PanelBox<'a>) -> Self::Context
-    where
-        I: AsRef<[&'a ProfileItem]>,
-    {
-        SourceContext { pbox: p }
-    }
-
-    fn render_header<B, I>(&'a self, f: &mut Frame<B>, items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>,
-    {
-        let txt = if items.as_ref().is_empty() {
-            Text::from("")
-        } else {
-            if items.as_ref()[0].is_file() {
-                Text::from("")
-            } else {
-                let spans = Spans::from(vec![
-                    Span::raw(""),
-                    Span::styled(
-                        format!(
-                            "In file {:?}",
-                            items.as_ref()[0].get_file_info().borrow().path.expand()
+impl<'a, 'i, B: Backend> Panel<'i, B> for SourcePanel<'a> {
+    fn render_header(&self, f: &mut Frame<B>, items: &[ViewItem<'i>], pbox: &PanelBox<'i>) {
+        let first = items.iter().find_map(|i| i.item());
+
+        let txt = match first {
+            None => Text::from(""),
+            Some(item) => {
+                if item.is_file() {
+                    Text::from("")
+                } else {
+                    let spans = Spans::from(vec![
+                        Span::raw(""),
+                        Span::styled(
+                            format!("In file {:?}", item.get_file_info().borrow().path.expand()),
+                            Style::default(),
                         ),
-                        Style::default(),
-                    ),
-                ]);
+                    ]);
 
-                Text::from(spans)
+                    Text::from(spans)
+                }
             }
         };
 
-        let p = Paragraph::new(txt).block(ctx.pbox.header.block.clone());
+        let p = Paragraph::new(txt).block(pbox.header.block.clone());
 
-        f.render_widget(p, ctx.pbox.header.rect);
+        f.render_widget(p, pbox.header.rect);
     }
 
-    fn render_body<B, I>(&'a self, f: &mut Frame<B>, items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>,
-    {
+    fn render_body(&self, f: &mut Frame<B>, items: &[ViewItem<'i>], pbox: &PanelBox<'i>) {
         let mut checkpoint_lines: Vec<Spans> = vec![];
+        let width = pbox.body.block.inner(pbox.body.rect).width as usize;
 
-        for item in items.as_ref().clone() {
-            checkpoint_lines.push(render_code_line(item));
+        for item in items {
+            checkpoint_lines.push(match item {
+                ViewItem::Item(item) => render_code_line(item, self, width),
+                ViewItem::Fold(len) => render_fold(*len, &self.theme),
+            });
         }
 
-        let p = Paragraph::new(Text::from(checkpoint_lines)).block(ctx.pbox.body.block.clone());
-        f.render_widget(p, ctx.pbox.body.rect);
+        let p = Paragraph::new(Text::from(checkpoint_lines)).block(pbox.body.block.clone());
+        f.render_widget(p, pbox.body.rect);
     }
 
-    fn render_help<B, I>(&'a self, f: &mut Frame<B>, _items: I, ctx: &Self::Context)
-    where
-        B: Backend,
-        I: AsRef<[&'a ProfileItem]>,
-    {
-        let w = help_widget(&self.help).block(ctx.pbox.footer.block.clone());
+    fn render_help(&self, f: &mut Frame<B>, _items: &[ViewItem<'i>], pbox: &PanelBox<'i>) {
+        let w = help_widget(&self.help).block(pbox.footer.block.clone());
 
-        f.render_widget(w, ctx.pbox.footer.rect);
+        f.render_widget(w, pbox.footer.rect);
     }
 }
@@ -0,0 +1,53 @@
+use tui::{
+    backend::Backend,
+    layout::Rect,
+    style::Modifier,
+    text::{Span, Spans, Text},
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use crate::model::stats::Stats;
+
+use super::theme::Theme;
+
+/// The summary of the profile, over the panels, from the line `offset`.
+/// Returns the offset actually used, so that the last line stays at the bottom.
+pub fn render_stats<B: Backend>(
+    f: &mut Frame<B>,
+    rect: Rect,
+    stats: &Stats,
+    offset: usize,
+    theme: &Theme,
+) -> usize {
+    let text = stats.to_string();
+    let lines: Vec<Spans> = text
+        .lines()
+        .map(|line| {
+            // Column headers
+            if line.ends_with("shared next") || line.ends_with("largest functions") {
+                Spans::from(Span::styled(
+                    String::from(line),
+                    theme.dimmed.add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Spans::from(String::from(line))
+            }
+        })
+        .collect();
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(" Statistics [Esc] Close ");
+
+    // Inside the borders
+    let height = rect.height.saturating_sub(2) as usize;
+    let offset = usize::min(offset, lines.len().saturating_sub(height));
+
+    let p = Paragraph::new(Text::from(lines))
+        .block(block)
+        .scroll((offset as u16, 0));
+    f.render_widget(p, rect);
+
+    offset
+}
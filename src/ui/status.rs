@@ -0,0 +1,133 @@
+use crate::controller::{command::CommandLine, App};
+use crate::model::profile::ProfileItem;
+use crate::ui::theme::Theme;
+
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Span, Spans},
+    widgets::Paragraph,
+    Frame,
+};
+
+/// Left part: profile, then either the transient message or the position in the code
+fn location<'a>(app: &App) -> Spans<'a> {
+    let mut spans = vec![Span::styled(
+        format!(" {} ", app.profile_path.display()),
+        app.theme.status.add_modifier(Modifier::BOLD),
+    )];
+
+    if let Some(message) = app.get_message() {
+        let style = if app.is_error() {
+            app.theme.error
+        } else {
+            app.theme.message
+        };
+
+        spans.push(Span::styled(format!(" {} ", message), style));
+
+        return Spans::from(spans);
+    }
+
+    if let Some(item) = app.get_current_item() {
+        let mut location = format!(" {}", item.get_file_info().borrow().path.expand().display());
+
+        if let Some(function) = app.get_current_function() {
+            location.push_str(&format!(" in {}()", function));
+        }
+
+        spans.push(Span::styled(location, app.theme.status));
+    }
+
+    Spans::from(spans)
+}
+
+/// Right part: checkpoints met on the current line, active modes and line number
+fn position<'a>(app: &App) -> Spans<'a> {
+    let mut spans = vec![];
+
+    if let Some(ProfileItem::Line(_, l)) = app.get_current_item() {
+        if !l.checkpoints.is_empty() {
+            let names: Vec<String> = l
+                .checkpoints
+                .iter()
+                .map(|c| match app.profile.checkpoint_name(*c) {
+                    Some(name) => format!("{}:{}", c, name),
+                    None => c.to_string(),
+                })
+                .collect();
+
+            spans.push(Span::styled(
+                format!(" {} {} ", app.theme.met_glyph, names.join(", ")),
+                app.theme.status.patch(app.theme.met),
+            ));
+        }
+    }
+
+    for mode in app.get_modes() {
+        spans.push(Span::styled(
+            format!(" [{}] ", mode),
+            app.theme.status.add_modifier(Modifier::ITALIC),
+        ));
+    }
+
+    let line = match app.get_current_item() {
+        Some(ProfileItem::Line(_, l)) => match app.get_current_file_length() {
+            Some(len) => format!(" line {} of {} ", l.nb, len),
+            None => format!(" line {} ", l.nb),
+        },
+        _ => String::from(" -- "),
+    };
+
+    spans.push(Span::styled(
+        line,
+        app.theme.status.add_modifier(Modifier::BOLD),
+    ));
+
+    Spans::from(spans)
+}
+
+/// The command line replaces the status bar while it is open
+fn render_command_line<B: Backend>(
+    f: &mut Frame<B>,
+    rect: Rect,
+    line: &CommandLine,
+    theme: &Theme,
+) {
+    let mut spans = vec![
+        Span::raw(format!(":{}", line.input)),
+        Span::styled(" ", Style::default().add_modifier(Modifier::REVERSED)),
+    ];
+
+    if !line.candidates().is_empty() {
+        spans.push(Span::styled(
+            format!("   {}", line.candidates().join(" ")),
+            theme.dimmed,
+        ));
+    }
+
+    f.render_widget(Paragraph::new(Spans::from(spans)), rect);
+}
+
+pub fn render_status_bar<B: Backend>(f: &mut Frame<B>, rect: Rect, app: &App) {
+    if let Some(line) = app.get_command_line() {
+        return render_command_line(f, rect, line, &app.theme);
+    }
+
+    let right = position(app);
+    let right_width = right.width() as u16;
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(right_width)].as_ref())
+        .split(rect);
+
+    let left = Paragraph::new(location(app)).style(app.theme.status);
+    let right = Paragraph::new(right)
+        .style(app.theme.status)
+        .alignment(Alignment::Right);
+
+    f.render_widget(left, chunks[0]);
+    f.render_widget(right, chunks[1]);
+}
@@ -0,0 +1,142 @@
+use tui::text::Span;
+use unicode_width::UnicodeWidthChar;
+
+/// Marker displayed in place of the last column of a truncated line
+pub const TRUNCATION_MARKER: char = '›';
+
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
+
+/// Replace tabs by spaces, up to the next multiple of `tab_width`.
+/// `col` is the display column at which `s` starts.
+pub fn expand_tabs(s: &str, tab_width: usize, col: usize) -> String {
+    let mut ret = String::with_capacity(s.len());
+    let mut col = col;
+
+    for c in s.chars() {
+        if c == '\t' {
+            let n = if tab_width == 0 {
+                0
+            } else {
+                tab_width - (col % tab_width)
+            };
+
+            ret.push_str(&" ".repeat(n));
+            col += n;
+        } else {
+            ret.push(c);
+            col += char_width(c);
+        }
+    }
+
+    ret
+}
+
+/// Display width of a string, tabs being expanded
+pub fn display_width(s: &str, tab_width: usize) -> usize {
+    expand_tabs(s, tab_width, 0).chars().map(char_width).sum()
+}
+
+/// Expand the tabs of a sequence of spans forming a single line
+pub fn expand_spans_tabs<'a>(spans: Vec<Span<'a>>, tab_width: usize) -> Vec<Span<'a>> {
+    let mut col = 0;
+
+    spans
+        .into_iter()
+        .map(|span| {
+            let content = expand_tabs(&span.content, tab_width, col);
+            col += content.chars().map(char_width).sum::<usize>();
+            Span::styled(content, span.style)
+        })
+        .collect()
+}
+
+/// Keep the `width` display columns of a line starting at column `offset`.
+/// Wide characters cut by the clipping window are replaced by spaces.
+/// If the line goes past the window, its last column is replaced by `TRUNCATION_MARKER`.
+pub fn clip_spans<'a>(spans: Vec<Span<'a>>, offset: usize, width: usize) -> Vec<Span<'a>> {
+    let total: usize = spans
+        .iter()
+        .map(|s| s.content.chars().map(char_width).sum::<usize>())
+        .sum();
+    let truncated = total > offset + width;
+    let end = if truncated {
+        offset + width.saturating_sub(1)
+    } else {
+        offset + width
+    };
+
+    let mut ret = vec![];
+    let mut col = 0;
+
+    for span in spans {
+        let mut content = String::new();
+
+        for c in span.content.chars() {
+            let w = char_width(c);
+
+            if col >= offset && col + w <= end {
+                content.push(c);
+            } else {
+                // Blank out the visible part of a cut wide character
+                for x in col..col + w {
+                    if x >= offset && x < end {
+                        content.push(' ');
+                    }
+                }
+            }
+
+            col += w;
+        }
+
+        if !content.is_empty() {
+            ret.push(Span::styled(content, span.style));
+        }
+    }
+
+    if truncated && width > 0 {
+        let style = ret.last().map(|s: &Span| s.style).unwrap_or_default();
+        ret.push(Span::styled(TRUNCATION_MARKER.to_string(), style));
+    }
+
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clip_spans, display_width, expand_tabs};
+    use tui::text::Span;
+
+    fn content(spans: Vec<Span>) -> String {
+        spans.iter().map(|s| s.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn tabs() {
+        assert_eq!(expand_tabs("\tx", 4, 0), "    x");
+        assert_eq!(expand_tabs("ab\tx", 4, 0), "ab  x");
+        assert_eq!(expand_tabs("ab\tx", 4, 1), "ab x");
+        assert_eq!(expand_tabs("abcd\tx", 4, 0), "abcd    x");
+        assert_eq!(display_width("a\t日本", 8), 12);
+    }
+
+    #[test]
+    fn clip() {
+        let spans = vec![Span::raw("int "), Span::raw("main()")];
+
+        assert_eq!(content(clip_spans(spans.clone(), 0, 20)), "int main()");
+        assert_eq!(content(clip_spans(spans.clone(), 0, 6)), "int m›");
+        assert_eq!(content(clip_spans(spans.clone(), 4, 6)), "main()");
+        assert_eq!(content(clip_spans(spans, 4, 3)), "ma›");
+    }
+
+    #[test]
+    fn clip_wide_chars() {
+        let spans = vec![Span::raw("日本語")];
+
+        assert_eq!(content(clip_spans(spans.clone(), 0, 4)), "日 ›");
+        assert_eq!(content(clip_spans(spans.clone(), 1, 4)), " 本›");
+        assert_eq!(content(clip_spans(spans, 0, 6)), "日本語");
+    }
+}
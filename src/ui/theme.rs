@@ -0,0 +1,330 @@
+use serde::Deserialize;
+use std::{collections::BTreeMap, convert::TryFrom};
+use tui::style::{Color, Modifier, Style};
+
+use super::highlight::SyntaxTheme;
+
+/// Names of the built-in themes
+pub const THEMES: [&str; 4] = ["default", "high-contrast", "monochrome", "deuteranopia"];
+
+/// Styles of the interface, shared by the panels and the status bar.
+/// The matrix glyphs alone must tell met from unmet checkpoints, colours only help.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    /// Cell of a checkpoint met by the line
+    pub met: Style,
+    /// Cell of a checkpoint not met by the line
+    pub not_met: Style,
+    pub met_glyph: char,
+    pub not_met_glyph: char,
+    /// Line numbers of the source panel
+    pub line_number: Style,
+    /// `[fl]` tag of the file headers
+    pub file_tag: Style,
+    /// Patched on the lines that met the focused checkpoint
+    pub focus: Style,
+    /// Patched on the lines changed by a reload
+    pub flash: Style,
+    /// Status bar
+    pub status: Style,
+    /// Messages of the status bar
+    pub message: Style,
+    /// Error messages of the status bar
+    pub error: Style,
+    /// Secondary text: folds, functions without source, completions
+    pub dimmed: Style,
+    pub syntax: SyntaxTheme,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            met: Style::default().fg(Color::LightGreen),
+            not_met: Style::default().fg(Color::Gray),
+            met_glyph: '◼',
+            not_met_glyph: '·',
+            line_number: Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+            file_tag: Style::default().bg(Color::Green),
+            focus: Style::default().bg(Color::Indexed(17)),
+            flash: Style::default().bg(Color::Yellow).fg(Color::Black),
+            status: Style::default().bg(Color::DarkGray).fg(Color::White),
+            message: Style::default()
+                .bg(Color::Yellow)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            error: Style::default()
+                .bg(Color::Red)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            dimmed: Style::default()
+                .fg(Color::DarkGray)
+                .add_modifier(Modifier::ITALIC),
+            syntax: SyntaxTheme::default(),
+        }
+    }
+}
+
+impl Theme {
+    pub fn high_contrast() -> Theme {
+        Theme {
+            met: Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            not_met: Style::default().fg(Color::DarkGray),
+            line_number: Style::default().bg(Color::Black).fg(Color::White),
+            file_tag: Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            focus: Style::default().bg(Color::Blue),
+            status: Style::default().bg(Color::Black).fg(Color::White),
+            message: Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            error: Style::default()
+                .bg(Color::Red)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD),
+            dimmed: Style::default().fg(Color::Gray),
+            syntax: SyntaxTheme {
+                keyword: Style::default()
+                    .fg(Color::LightMagenta)
+                    .add_modifier(Modifier::BOLD),
+                type_name: Style::default().fg(Color::LightCyan),
+                string: Style::default().fg(Color::LightYellow),
+                number: Style::default().fg(Color::LightRed),
+                comment: Style::default()
+                    .fg(Color::Gray)
+                    .add_modifier(Modifier::ITALIC),
+                preprocessor: Style::default().fg(Color::LightBlue),
+                macro_name: Style::default().fg(Color::LightBlue),
+                label: Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+                register: Style::default().fg(Color::LightCyan),
+            },
+            ..Theme::default()
+        }
+    }
+
+    /// No colour at all, only glyphs and modifiers
+    pub fn monochrome() -> Theme {
+        Theme {
+            met: Style::default().add_modifier(Modifier::BOLD),
+            not_met: Style::default(),
+            met_glyph: '◼',
+            not_met_glyph: '·',
+            line_number: Style::default().add_modifier(Modifier::ITALIC),
+            file_tag: Style::default().add_modifier(Modifier::REVERSED),
+            focus: Style::default().add_modifier(Modifier::UNDERLINED),
+            flash: Style::default().add_modifier(Modifier::BOLD | Modifier::ITALIC),
+            status: Style::default().add_modifier(Modifier::REVERSED),
+            message: Style::default().add_modifier(Modifier::BOLD),
+            error: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            dimmed: Style::default().add_modifier(Modifier::ITALIC),
+            syntax: SyntaxTheme {
+                keyword: Style::default().add_modifier(Modifier::BOLD),
+                type_name: Style::default(),
+                string: Style::default(),
+                number: Style::default(),
+                comment: Style::default().add_modifier(Modifier::ITALIC),
+                preprocessor: Style::default().add_modifier(Modifier::BOLD),
+                macro_name: Style::default(),
+                label: Style::default().add_modifier(Modifier::BOLD),
+                register: Style::default(),
+            },
+        }
+    }
+
+    /// Blue and orange instead of green and red
+    pub fn deuteranopia() -> Theme {
+        let orange = Color::Indexed(208);
+
+        Theme {
+            met: Style::default()
+                .fg(Color::Indexed(39))
+                .add_modifier(Modifier::BOLD),
+            not_met: Style::default().fg(Color::Gray),
+            file_tag: Style::default().bg(Color::Indexed(25)),
+            flash: Style::default().bg(orange).fg(Color::Black),
+            message: Style::default()
+                .bg(Color::Indexed(153))
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            error: Style::default()
+                .bg(orange)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+            syntax: SyntaxTheme {
+                number: Style::default().fg(orange),
+                ..SyntaxTheme::default()
+            },
+            ..Theme::default()
+        }
+    }
+
+    /// One of the built-in themes, by name
+    pub fn named(name: &str) -> Result<Theme, String> {
+        match name {
+            "default" => Ok(Theme::default()),
+            "high-contrast" => Ok(Theme::high_contrast()),
+            "monochrome" => Ok(Theme::monochrome()),
+            "deuteranopia" => Ok(Theme::deuteranopia()),
+            _ => Err(format!(
+                "unknown theme {:?}, expected one of {}",
+                name,
+                THEMES.join(", ")
+            )),
+        }
+    }
+
+    /// The style of a role, and whether its colour is the background
+    fn role_mut(&mut self, name: &str) -> Option<(&mut Style, bool)> {
+        match name {
+            "met" => Some((&mut self.met, false)),
+            "not_met" => Some((&mut self.not_met, false)),
+            "line_number" => Some((&mut self.line_number, true)),
+            "file_tag" => Some((&mut self.file_tag, true)),
+            "focus" => Some((&mut self.focus, true)),
+            "flash" => Some((&mut self.flash, true)),
+            "status" => Some((&mut self.status, true)),
+            "message" => Some((&mut self.message, true)),
+            "error" => Some((&mut self.error, true)),
+            "dimmed" => Some((&mut self.dimmed, false)),
+            _ => None,
+        }
+    }
+
+    /// Replace some colours, given by role
+    pub fn with_colors(mut self, colors: &BTreeMap<String, ColorValue>) -> Result<Theme, String> {
+        for (name, value) in colors {
+            match self.role_mut(name) {
+                Some((style, true)) => *style = style.bg(value.0),
+                Some((style, false)) => *style = style.fg(value.0),
+                None => {
+                    return Err(format!(
+                        "unknown colour {:?}, expected one of met, not_met, line_number, \
+                         file_tag, focus, flash, status, message, error or dimmed",
+                        name
+                    ))
+                }
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// Whether colours are disabled with the `NO_COLOR` environment variable
+/// (see https://no-color.org)
+pub fn no_color() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+/// A colour read from the configuration: a name (`light-green`), a 256 colours palette
+/// index (`17`) or a RGB value (`#1e90ff`)
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ColorValue(pub Color);
+
+impl TryFrom<String> for ColorValue {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let color = match s.as_str() {
+            "reset" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" => Color::Gray,
+            "dark-gray" => Color::DarkGray,
+            "light-red" => Color::LightRed,
+            "light-green" => Color::LightGreen,
+            "light-yellow" => Color::LightYellow,
+            "light-blue" => Color::LightBlue,
+            "light-magenta" => Color::LightMagenta,
+            "light-cyan" => Color::LightCyan,
+            "white" => Color::White,
+            _ => {
+                if let Some(hex) = s.strip_prefix('#') {
+                    match u32::from_str_radix(hex, 16) {
+                        Ok(rgb) if hex.len() == 6 => {
+                            Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+                        }
+                        _ => return Err(format!("invalid RGB colour {:?}", s)),
+                    }
+                } else if let Ok(index) = s.parse() {
+                    Color::Indexed(index)
+                } else {
+                    return Err(format!("unknown colour {:?}", s));
+                }
+            }
+        };
+
+        Ok(ColorValue(color))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColorValue, Theme, THEMES};
+    use std::{collections::BTreeMap, convert::TryFrom};
+    use tui::style::{Color, Style};
+
+    #[test]
+    fn parse_colors() {
+        let parse = |s: &str| ColorValue::try_from(String::from(s)).map(|c| c.0);
+
+        assert_eq!(parse("light-green"), Ok(Color::LightGreen));
+        assert_eq!(parse("17"), Ok(Color::Indexed(17)));
+        assert_eq!(parse("#1e90ff"), Ok(Color::Rgb(0x1e, 0x90, 0xff)));
+        assert!(parse("#1e90").is_err());
+        assert!(parse("grene").is_err());
+        assert!(parse("256").is_err());
+
+        let mut colors = BTreeMap::new();
+        colors.insert(String::from("met"), ColorValue(Color::Blue));
+        colors.insert(String::from("focus"), ColorValue(Color::Red));
+        let theme = Theme::default().with_colors(&colors).unwrap();
+        assert_eq!(theme.met.fg, Some(Color::Blue));
+        assert_eq!(theme.focus.bg, Some(Color::Red));
+
+        colors.insert(String::from("mett"), ColorValue(Color::Blue));
+        assert!(Theme::default().with_colors(&colors).is_err());
+    }
+
+    #[test]
+    fn named_themes() {
+        for name in THEMES.iter() {
+            let theme = Theme::named(name).unwrap();
+            assert_ne!(theme.met_glyph, theme.not_met_glyph);
+        }
+
+        assert!(Theme::named("solarized").is_err());
+
+        // Monochrome does not rely on colours at all
+        let theme = Theme::monochrome();
+        let styles: Vec<Style> = vec![
+            theme.met,
+            theme.not_met,
+            theme.line_number,
+            theme.file_tag,
+            theme.focus,
+            theme.flash,
+            theme.status,
+            theme.message,
+            theme.error,
+            theme.dimmed,
+            theme.syntax.keyword,
+            theme.syntax.comment,
+        ];
+        assert!(styles.iter().all(|s| s.fg.is_none() && s.bg.is_none()));
+    }
+}